@@ -2,17 +2,59 @@
 extern crate criterion;
 
 use ckb_vm::instructions::{b, i, m, rvc, v};
-use ckb_vm_decoder::{rvb_decoder, rvc_decoder, rvi_decoder, rvm_decoder, rvv_decoder};
+use ckb_vm_decoder::stats::MIX_HEADER;
+use ckb_vm_decoder::{
+    rvb_hash_decoder, rvb_tree_decoder, rvc_hash_decoder, rvc_tree_decoder, rvi_hash_decoder,
+    rvi_tree_decoder, rvm_hash_decoder, rvm_tree_decoder, rvv_hash_decoder, rvv_tree_decoder,
+};
 use criterion::{BenchmarkId, Criterion};
 use rand::prelude::*;
 
+// Size of the sample table built from a mix
+const MIX_SAMPLES: usize = 1 << 16;
+
+// With CKB_VM_DECODER_BENCH_MIX naming a file from `ckb-decode --stats
+// --bench-mix`, its words the decoder accepts, each repeated in proportion
+// to its count. None without the variable or when no word is for the decoder.
+fn load_mix(decodes: impl Fn(u32) -> bool) -> Option<Vec<u32>> {
+    let path = std::env::var("CKB_VM_DECODER_BENCH_MIX").ok()?;
+    let text = std::fs::read_to_string(&path).expect("cannot read the bench mix");
+    assert!(text.starts_with(MIX_HEADER), "{} is not a bench mix", path);
+    let words: Vec<(u32, u64)> = text
+        .lines()
+        .skip(1)
+        .map(|line| {
+            let mut fields = line.split_whitespace();
+            let bits = u32::from_str_radix(fields.next()?, 16).ok()?;
+            Some((bits, fields.next()?.parse().ok()?))
+        })
+        .map(|word| word.expect("malformed bench mix"))
+        .filter(|(bits, _)| decodes(*bits))
+        .collect();
+    let total: u64 = words.iter().map(|(_, count)| count).sum();
+    if total == 0 {
+        return None;
+    }
+    let mut samples = Vec::with_capacity(MIX_SAMPLES);
+    for (bits, count) in words {
+        let repeat = (count as u128 * MIX_SAMPLES as u128 / total as u128).max(1);
+        samples.extend((0..repeat).map(|_| bits));
+    }
+    Some(samples)
+}
+
 macro_rules! bench_rand_decode {
-    ($name:literal, $hash_based:ident, $hand_based:ident) => {
+    ($name:literal, $hash_based:ident, $tree_based:ident, $hand_based:ident) => {
         pub fn $hash_based(c: &mut Criterion) {
-            let gen_inst = |idx: usize| -> u32 {
-                let i = idx % $hash_based::INSTRUCTION_LIST.len();
-                $hash_based::INSTRUCTION_LIST[i].get_match_bits()
-            };
+            // INSTRUCTION_LIST sampled uniformly unless a mix is given
+            let samples = load_mix(|bits| $hash_based::factory::<u64>(bits, 0).is_some())
+                .unwrap_or_else(|| {
+                    $hash_based::INSTRUCTION_LIST
+                        .iter()
+                        .map(|info| info.get_match_bits())
+                        .collect()
+                });
+            let gen_inst = |idx: usize| -> u32 { samples[idx % samples.len()] };
 
             const ITERATION: usize = 100_000_00;
             let mut group = c.benchmark_group("group");
@@ -21,6 +63,7 @@ macro_rules! bench_rand_decode {
             let seed = rand::thread_rng().gen();
             let mut rng0 = SmallRng::from_seed(seed);
             let mut rng1 = SmallRng::from_seed(seed);
+            let mut rng2 = SmallRng::from_seed(seed);
 
             group.bench_function(BenchmarkId::new(concat!($name, "-hand-based"), 0), |b| {
                 b.iter(|| {
@@ -41,15 +84,25 @@ macro_rules! bench_rand_decode {
                     }
                 })
             });
+
+            group.bench_function(BenchmarkId::new(concat!($name, "-tree-based"), 0), |b| {
+                b.iter(|| {
+                    for _ in 0..ITERATION {
+                        let idx: usize = rng2.gen();
+                        let bits = gen_inst(idx);
+                        $tree_based::factory::<u64>(bits, 0);
+                    }
+                })
+            });
             group.finish();
         }
     };
 }
 
 macro_rules! bench_groups {
-    ($( ($name: literal, $hash_based:ident, $hand_based:ident )),*) => {
+    ($( ($name: literal, $hash_based:ident, $tree_based:ident, $hand_based:ident )),*) => {
         $(
-            bench_rand_decode!($name, $hash_based, $hand_based);
+            bench_rand_decode!($name, $hash_based, $tree_based, $hand_based);
         )*
         criterion_group!(
             benches,
@@ -59,10 +112,10 @@ macro_rules! bench_groups {
 }
 
 bench_groups!(
-    ("rvv", rvv_decoder, v),
-    ("rvb", rvb_decoder, b),
-    ("rvi", rvi_decoder, i),
-    ("rvm", rvm_decoder, m),
-    ("rvc", rvc_decoder, rvc)
+    ("rvv", rvv_hash_decoder, rvv_tree_decoder, v),
+    ("rvb", rvb_hash_decoder, rvb_tree_decoder, b),
+    ("rvi", rvi_hash_decoder, rvi_tree_decoder, i),
+    ("rvm", rvm_hash_decoder, rvm_tree_decoder, m),
+    ("rvc", rvc_hash_decoder, rvc_tree_decoder, rvc)
 );
 criterion_main!(benches);
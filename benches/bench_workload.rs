@@ -0,0 +1,154 @@
+#[macro_use]
+extern crate criterion;
+
+use ckb_vm::instructions::{b, i, m, rvc, v, Instruction};
+use ckb_vm_decoder::elf::executable_sections;
+use ckb_vm_decoder::{
+    rvb_hash_decoder, rvb_tree_decoder, rvc_hash_decoder, rvc_tree_decoder, rvi_hash_decoder,
+    rvi_tree_decoder, rvm_hash_decoder, rvm_tree_decoder, rvv_hash_decoder, rvv_tree_decoder,
+    FactoryConfig, InstructionMeta,
+};
+use criterion::{black_box, BenchmarkId, Criterion, Throughput};
+use rand::prelude::*;
+
+type Factory = fn(u32, u32) -> Option<Instruction>;
+
+const VERSION: u32 = 1;
+
+// ckb-vm's decoder hands every word to its factories in this order and takes
+// the first instruction, 2 byte words masked to their lower half
+const HAND_BASED: [Factory; 5] = [
+    rvc::factory::<u64>,
+    i::factory::<u64>,
+    m::factory::<u64>,
+    b::factory::<u64>,
+    v::factory::<u64>,
+];
+const HASH_BASED: [Factory; 5] = [
+    rvc_hash_decoder::factory::<u64>,
+    rvi_hash_decoder::factory::<u64>,
+    rvm_hash_decoder::factory::<u64>,
+    rvb_hash_decoder::factory::<u64>,
+    rvv_hash_decoder::factory::<u64>,
+];
+const TREE_BASED: [Factory; 5] = [
+    rvc_tree_decoder::factory::<u64>,
+    rvi_tree_decoder::factory::<u64>,
+    rvm_tree_decoder::factory::<u64>,
+    rvb_tree_decoder::factory::<u64>,
+    rvv_tree_decoder::factory::<u64>,
+];
+
+fn decode(factories: &[Factory], instruction_bits: u32) -> Option<Instruction> {
+    factories
+        .iter()
+        .find_map(|factory| factory(instruction_bits, VERSION))
+}
+
+const STREAM_LENGTH: usize = 1 << 16;
+
+// Percent of the mixed stream taken from each template, made up but with C
+// and I dominating as in compiled code. The rest are words no factory takes.
+const MIXED: [(&[InstructionMeta], u32); 5] = [
+    (&rvc_hash_decoder::INSTRUCTION_META, 45),
+    (&rvi_hash_decoder::INSTRUCTION_META, 40),
+    (&rvm_hash_decoder::INSTRUCTION_META, 4),
+    (&rvb_hash_decoder::INSTRUCTION_META, 4),
+    (&rvv_hash_decoder::INSTRUCTION_META, 2),
+];
+
+// Template entries with random operand bits, reserved encodings among them
+// are left in like the illegal words
+fn mixed_stream(rng: &mut SmallRng) -> Vec<u32> {
+    (0..STREAM_LENGTH)
+        .map(|_| {
+            let mut percent = rng.gen_range(0..100);
+            for (metas, share) in MIXED {
+                if percent < share {
+                    let meta = metas.choose(rng).unwrap();
+                    let word = if meta.match_bits & 0x3 != 0x3 {
+                        0xffff
+                    } else {
+                        u32::MAX
+                    };
+                    return meta.match_bits | (rng.gen::<u32>() & !meta.mask & word);
+                }
+                percent -= share;
+            }
+            loop {
+                let instruction_bits = rng.gen::<u32>() | 0x3;
+                if decode(&HASH_BASED, instruction_bits).is_none()
+                    && decode(&HAND_BASED, instruction_bits).is_none()
+                {
+                    return instruction_bits;
+                }
+            }
+        })
+        .collect()
+}
+
+// The code of the ELF files in benches/contracts, see the README there
+fn contracts() -> Vec<(String, Vec<u32>)> {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/benches/contracts");
+    let mut paths: Vec<_> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().and_then(|extension| extension.to_str()) != Some("md"))
+        .collect();
+    paths.sort();
+    let mut contracts = Vec::new();
+    for path in paths {
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        let elf_bytes = std::fs::read(&path).unwrap();
+        let sections = match executable_sections(&elf_bytes) {
+            Some(sections) => sections,
+            None => {
+                eprintln!("skipping {}, not an RV64 RISC-V ELF file", name);
+                continue;
+            }
+        };
+        let words = sections
+            .iter()
+            .flat_map(|section| section.words(&FactoryConfig::new::<u64>(VERSION)))
+            .map(|word| word.instruction_bits)
+            .collect();
+        contracts.push((name, words));
+    }
+    if contracts.is_empty() {
+        eprintln!(
+            "no contract binaries in {}, only the mixed stream runs",
+            dir
+        );
+    }
+    contracts
+}
+
+// Decodes the words in order like a VM walking the code
+pub fn workloads(c: &mut Criterion) {
+    let mut rng = SmallRng::seed_from_u64(0);
+    let mut workloads = vec![("mixed".to_string(), mixed_stream(&mut rng))];
+    workloads.extend(contracts());
+
+    let mut group = c.benchmark_group("workload");
+    group.sample_size(10);
+    for (name, words) in &workloads {
+        group.throughput(Throughput::Elements(words.len() as u64));
+        for (backend, factories) in [
+            ("hand-based", &HAND_BASED),
+            ("hash-based", &HASH_BASED),
+            ("tree-based", &TREE_BASED),
+        ] {
+            group.bench_with_input(BenchmarkId::new(backend, name), words, |b, words| {
+                b.iter(|| {
+                    for instruction_bits in words {
+                        black_box(decode(factories, *instruction_bits));
+                    }
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, workloads);
+criterion_main!(benches);
@@ -1,33 +1,279 @@
 // build.rs
 
-use std::path::Path;
-use std::process::Command;
+// The salt search of the hash decoders, see gen_table()
+#[allow(dead_code)]
+#[path = "src/perfect_hash.rs"]
+mod perfect_hash;
+
+// Template parsing and decoder generation, see codegen()
+#[path = "build/codegen.rs"]
+mod codegen;
+
+use codegen::Template;
+use perfect_hash::{PerfectHash, Reduction};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
 use std::{env, fs, io::Write};
 
+// Seed of the salt search, override with CKB_VM_DECODER_SEED to try
+// other hash tables. The same seed always generates the same decoders.
+const DEFAULT_SEED: u64 = 0;
+
+// Opcode definitions checked against the templates, see check_opcodes
+const CKB_VM_OPCODES: &str = "ckb-vm/definitions/src/instructions.rs";
+const LOCAL_OPCODES: &str = "src/opcodes.rs";
+
+// ckb-vm opcodes no encoding decodes to: macro-op fusion results and
+// opcodes reserved for the machine itself. Matched by prefix. Any other
+// opcode without a template entry fails the build, see check_opcodes.
+const INTERNAL_OPCODES: [&str; 9] = [
+    "OP_UNLOADED",
+    "OP_CUSTOM_",
+    "OP_FAR_JUMP_",
+    "OP_WIDE_",
+    "OP_ADD3",
+    "OP_ADC",
+    "OP_SBB",
+    "OP_MOP_",
+    "OP_LD_SIGN_EXTENDED",
+];
+
+// Every opcode override of the templates, (entry, opcode). Anything else
+// must decode to the opcode of its own name, HINTs to that of the
+// instruction they are named after.
+const OPCODE_ALIASES: [(&str, &str); 16] = [
+    ("ADDI16SP", "ADDI"),
+    ("ADDI4SPN", "ADDI"),
+    ("BEQZ", "BEQ"),
+    ("BNEZ", "BNE"),
+    ("J", "JAL"),
+    ("JR", "JALR"),
+    ("LDSP", "LD"),
+    ("LI", "ADDI"),
+    ("LI_HINT", "ADDI"),
+    ("LWSP", "LW"),
+    ("MV", "ADD"),
+    ("MV_HINT", "ADD"),
+    ("NOP", "ADDI"),
+    ("SDSP", "SD"),
+    ("SLLI64", "SLLI"),
+    ("SWSP", "SW"),
+];
+
+fn is_internal(opcode: &str) -> bool {
+    INTERNAL_OPCODES
+        .iter()
+        .any(|prefix| opcode.starts_with(prefix))
+}
+
+fn parse_opcodes(path: &str) -> Option<BTreeSet<String>> {
+    let source = fs::read_to_string(path).ok()?;
+    let opcodes = source
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("pub const "))
+        .filter_map(|line| line.split(':').next())
+        .map(str::trim)
+        .filter(|name| name.starts_with("OP_"))
+        .map(String::from)
+        .collect();
+    Some(opcodes)
+}
+
+// (name, value) of the opcodes defined by a literal, e.g.
+// `pub const OP_VFADD_VF: InstructionOpcode = 0x1000;`
+fn parse_opcode_values(path: &str) -> Vec<(String, u32)> {
+    let source = fs::read_to_string(path).unwrap_or_default();
+    source
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("pub const OP_"))
+        .filter_map(|line| {
+            let (name, value) = line.split_once('=')?;
+            let name = name.split(':').next()?.trim();
+            let value = value.trim().trim_end_matches(';').replace('_', "");
+            let value = match value.strip_prefix("0x") {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => value.parse().ok()?,
+            };
+            Some((format!("OP_{}", name), value))
+        })
+        .collect()
+}
+
+// The opcodes of src/opcodes.rs share InstructionOpcode with ckb-vm, none
+// may take a value ckb-vm or another local opcode already uses
+fn check_opcode_values() {
+    let mut used: BTreeMap<u32, String> = parse_opcode_values(CKB_VM_OPCODES)
+        .into_iter()
+        .map(|(name, value)| (value, name))
+        .collect();
+    let mut errors = Vec::new();
+    for (name, value) in parse_opcode_values(LOCAL_OPCODES) {
+        if let Some(other) = used.insert(value, name.clone()) {
+            errors.push(format!("{} and {} are both {:#x}", name, other, value));
+        }
+    }
+    if !errors.is_empty() {
+        panic!("Opcode values collide:\n{}", errors.join("\n"));
+    }
+}
+
+// (entry name, opcode name) of each template entry. The opcode is the
+// optional 5th column, or the entry name itself.
+fn template_opcodes(path: &Path) -> Vec<(String, String)> {
+    let source = fs::read_to_string(path).unwrap();
+    let mut entries = Vec::new();
+    for line in source.lines().skip(1) {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("layout") {
+            continue;
+        }
+        let columns: Vec<&str> = line
+            .split(',')
+            .map(str::trim)
+            .take_while(|column| !column.contains('=') && !column.starts_with('"'))
+            .collect();
+        let opcode = columns.get(4).unwrap_or(&columns[0]);
+        entries.push((columns[0].to_string(), opcode.to_string()));
+    }
+    entries
+}
+
+// Every opcode a template refers to must exist, either in ckb-vm or in
+// src/opcodes.rs, and an opcode override must be listed in OPCODE_ALIASES.
+// Every opcode of either file needs a template entry decoding
+// to it unless INTERNAL_OPCODES lists it.
+fn check_opcodes(templates: &[PathBuf]) {
+    let ckb_vm = match parse_opcodes(CKB_VM_OPCODES) {
+        Some(opcodes) => opcodes,
+        None => {
+            println!(
+                "cargo:warning=Skipping opcode check, {} not found",
+                CKB_VM_OPCODES
+            );
+            return;
+        }
+    };
+    let local = parse_opcodes(LOCAL_OPCODES).unwrap();
+    let mut errors = Vec::new();
+    let mut referenced = BTreeSet::new();
+    for template in templates {
+        for (name, opcode) in template_opcodes(template) {
+            if name != opcode
+                && name.strip_suffix("_HINT") != Some(opcode.as_str())
+                && !OPCODE_ALIASES.contains(&(name.as_str(), opcode.as_str()))
+            {
+                errors.push(format!(
+                    "{}: {} decodes to unrelated opcode {}",
+                    template.display(),
+                    name,
+                    opcode
+                ));
+            }
+            let opcode = format!("OP_{}", opcode);
+            if !ckb_vm.contains(&opcode) && !local.contains(&opcode) {
+                errors.push(format!("{}: {} is not defined", template.display(), opcode));
+            }
+            referenced.insert(opcode);
+        }
+    }
+    for opcode in ckb_vm.iter().chain(local.iter()) {
+        if !referenced.contains(opcode) && !is_internal(opcode) {
+            errors.push(format!(
+                "{} has no decoder entry, add one or list it in INTERNAL_OPCODES",
+                opcode
+            ));
+        }
+    }
+    if !errors.is_empty() {
+        panic!(
+            "Template opcodes do not match definitions:\n{}",
+            errors.join("\n")
+        );
+    }
+}
+
+// Writes <out_dir>/opcode_names.rs for src/disasm.rs, the name of every
+// opcode a decoder can return
+fn gen_opcode_names(out_dir: &Path) {
+    let mut opcodes = parse_opcodes(CKB_VM_OPCODES).unwrap_or_default();
+    opcodes.extend(parse_opcodes(LOCAL_OPCODES).unwrap());
+    let mut code = String::from(
+        "pub fn opcode_name(opcode: insts::InstructionOpcode) -> Option<&'static str> {\n    match opcode {\n",
+    );
+    for opcode in opcodes.iter().filter(|opcode| !is_internal(opcode)) {
+        code += &format!(
+            "        insts::{} => Some({:?}),\n",
+            opcode,
+            &opcode["OP_".len()..]
+        );
+    }
+    code += "        _ => None,\n    }\n}\n";
+    fs::write(out_dir.join("opcode_names.rs"), code).expect("Could not write opcode_names.rs");
+}
+
+// Salt pairs tried per table size when searching for a smaller G
+const CANDIDATES: usize = 1000;
+
+// Finds the perfect hash table for the keys of template. Power of two
+// tables come out smaller than the modulo ones for all but rvv, and their
+// lookup needs no division. Take the smaller one, the power of two one on a
+// tie.
+fn gen_table(template: &Template, seed: u64) -> PerfectHash {
+    let keys = template.keys();
+    [Reduction::Pow2, Reduction::Modulo]
+        .into_iter()
+        .map(|reduction| PerfectHash::search(&keys, seed, reduction, CANDIDATES))
+        .min_by_key(|hash| hash.as_ref().map(PerfectHash::ng))
+        .flatten()
+        .unwrap_or_else(|| panic!("templates/{} has duplicate keys", template.basename))
+}
+
+// Generates <out_dir>/<module>.rs, a perfect hash decoder unless tree is
+// set, and returns its declaration for mod.rs
+fn codegen(template: &Template, out_dir: &Path, module: &str, tree: bool, seed: u64) -> String {
+    let output = out_dir.join(format!("{}.rs", module));
+    let hash = (!tree).then(|| gen_table(template, seed));
+    let code = template.gen_decoder(module, hash.as_ref(), seed);
+    fs::write(&output, code).expect("Could not write the decoder");
+    // mod.rs is include!d from src/lib.rs, point at the file explicitly
+    format!(
+        "#[path = {:?}]\npub mod {};",
+        output.to_str().unwrap(),
+        module
+    )
+}
+
 fn main() {
-    let mut path = env::current_dir().unwrap();
-    path.push("codegen.py");
-    let codegen_bin = path.to_str().unwrap();
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=build");
+    println!("cargo:rerun-if-changed=src/perfect_hash.rs");
+    println!("cargo:rerun-if-changed=templates");
+    println!("cargo:rerun-if-changed={}", CKB_VM_OPCODES);
+    println!("cargo:rerun-if-changed={}", LOCAL_OPCODES);
+    println!("cargo:rerun-if-env-changed=CKB_VM_DECODER_SEED");
+    let seed = match env::var("CKB_VM_DECODER_SEED") {
+        Ok(seed) => seed
+            .parse::<u64>()
+            .expect("CKB_VM_DECODER_SEED is not a number"),
+        Err(_) => DEFAULT_SEED,
+    };
 
-    // for each templates, perform codegen
-    let template_dir = fs::read_dir("templates/").unwrap();
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+
+    // for each templates, perform codegen. read_dir order is unspecified,
+    // sort it so mod.rs is stable too.
+    let mut template_dir: Vec<_> = fs::read_dir("templates/").unwrap().collect();
+    template_dir.sort_by_key(|template| template.as_ref().unwrap().path());
+    let templates: Vec<PathBuf> = template_dir
+        .iter()
+        .map(|template| template.as_ref().unwrap().path())
+        .collect();
+    check_opcodes(&templates);
+    check_opcode_values();
+    gen_opcode_names(&out_dir);
     let mut modules = Vec::new();
-    for template in template_dir {
-        let status = Command::new(codegen_bin)
-            .args([
-                template.as_ref().unwrap().path().to_str().unwrap(),
-                "--dir",
-                "src",
-            ])
-            .current_dir(env::current_dir().unwrap())
-            .status()
-            .expect("Failed to execute codegen");
-        if !status.success() {
-            panic!("Failed to generate decoder for {:?}", template);
-        }
+    for template in &templates {
         let basename = template
-            .unwrap()
-            .path()
             .file_stem()
             .unwrap()
             .to_str()
@@ -35,10 +281,32 @@ fn main() {
             .split('.')
             .next()
             .unwrap()
-            .to_string() + "_decoder;";
-        modules.push(format!("pub mod {}", basename));
+            .to_string();
+        // every template has a feature of its name, only those enabled are
+        // generated and compiled
+        let feature = format!("CARGO_FEATURE_{}", basename.to_uppercase());
+        if env::var_os(&feature).is_none() {
+            continue;
+        }
+        // e.g. the rvi-tree feature decodes rvi with a match tree
+        let tree = env::var_os(feature + "_TREE").is_some();
+        let module = format!("{}_decoder", basename);
+        let template = Template::parse(template);
+        let generate = |module: &str, tree: bool| codegen(&template, &out_dir, module, tree, seed);
+        if env::var_os("CARGO_FEATURE_BACKENDS").is_some() {
+            // both backends side by side for benches/bench_decode.rs, the
+            // selected one is still reachable as <base>_decoder
+            let hash_module = format!("{}_hash_decoder", basename);
+            let tree_module = format!("{}_tree_decoder", basename);
+            modules.push(generate(&hash_module, false));
+            modules.push(generate(&tree_module, true));
+            let backend = if tree { tree_module } else { hash_module };
+            modules.push(format!("pub use {} as {};", backend, module));
+        } else {
+            modules.push(generate(&module, tree));
+        }
     }
-    let mod_dest = Path::new("src").join("mod.rs");
+    let mod_dest = out_dir.join("mod.rs");
     let mut f = fs::File::create(&mod_dest).expect("Could not create mod.rs");
     write!(&mut f, "{}", modules.join("\n")).expect("Could not write to file");
 }
@@ -0,0 +1,1402 @@
+// Decoder generator, run by build.rs for every file of templates/. It parses
+// a template and writes the Rust code of its decoder: the factory, either as
+// a perfect hash lookup with the table build.rs finds or as a match tree,
+// the instruction metadata, the encoders and their tests.
+//
+// Template format:
+// First line: set_instruction_length_n
+// Rest: instruction_name,mask,match_bits,handler[,opcode_name]?[,key=value]*
+//       or layout,name,spec which generates name() and encode_name() for
+//       handlers to use, see Layout
+//       or a comment starting with #
+// Named columns, all optional:
+//   fmt=         instruction format, e.g. R, CI, OPIVV
+//   xlen=        32 or 64 if the entry only exists there, enforced by the factory
+//   min_version= lowest FactoryConfig version decoding it, enforced by the factory
+//   ext=         owning extension, defaults to the template name without "rv"
+//   asm=         operand syntax as objdump prints it, e.g. rd,imm(rs1), with
+//                the placeholders of ASM_FIELDS, quote the column for commas
+//   reads=       register operands read, defaults to those of the format, see
+//                parse_operands and FORMAT_OPERANDS
+//   writes=      register operands written, likewise
+use super::perfect_hash::{PerfectHash, Reduction};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+// Named columns a template line may carry after the positional ones
+const COLUMNS: [&str; 7] = [
+    "fmt",
+    "xlen",
+    "min_version",
+    "ext",
+    "asm",
+    "reads",
+    "writes",
+];
+
+// Register operands (reads, writes) of each format, for entries without the
+// reads= and writes= columns. Fields the mask of an entry fixes are dropped,
+// e.g. vs1 of vmv.x.s, so only the exceptions need the columns.
+const FORMAT_OPERANDS: [(&str, &str, &str); 29] = [
+    ("R", "x:rs1 x:rs2", "x:rd"),
+    ("I", "x:rs1", "x:rd"),
+    ("S", "x:rs1 x:rs2", ""),
+    ("B", "x:rs1 x:rs2", ""),
+    ("U", "", "x:rd"),
+    ("J", "", "x:rd"),
+    ("CR", "x:rd x:rs2", "x:rd"),
+    ("CI", "x:rd", "x:rd"),
+    ("CIW", "x:sp", "x:rd'"),
+    ("CL", "x:rs1'", "x:rd'"),
+    ("CS", "x:rs1' x:rs2'", ""),
+    ("CSS", "x:sp x:rs2", ""),
+    ("CA", "x:rs1' x:rs2'", "x:rs1'"),
+    ("CB", "x:rs1'", "x:rs1'"),
+    ("CJ", "", ""),
+    ("OPIVV", "v:rs1 v:rs2 v:mask", "v:rd"),
+    ("OPFVV", "v:rs1 v:rs2 v:mask", "v:rd"),
+    ("OPMVV", "v:rs1 v:rs2 v:mask", "v:rd"),
+    ("OPIVI", "v:rs2 v:mask", "v:rd"),
+    ("OPIVX", "x:rs1 v:rs2 v:mask", "v:rd"),
+    ("OPMVX", "x:rs1 v:rs2 v:mask", "v:rd"),
+    ("OPFVF", "f:rs1 v:rs2 v:mask", "v:rd"),
+    ("OPCFG", "x:rs1 x:rs2", "x:rd"),
+    ("VL", "x:rs1 v:mask", "v:rd"),
+    ("VLS", "x:rs1 x:rs2 v:mask", "v:rd"),
+    ("VLX", "x:rs1 v:rs2 v:mask", "v:rd"),
+    ("VS", "v:rd x:rs1 v:mask", ""),
+    ("VSS", "v:rd x:rs1 x:rs2 v:mask", ""),
+    ("VSX", "v:rd x:rs1 v:rs2 v:mask", ""),
+];
+
+// Placeholders the asm= column may use per format, see disasm::operands
+const ASM_FIELDS: [(&str, &[&str]); 6] = [
+    ("R", &["rd", "rs1", "rs2"]),
+    ("I", &["rd", "rs1", "imm", "shamt", "pred", "succ"]),
+    ("S", &["rs1", "rs2", "imm"]),
+    ("B", &["rs1", "rs2", "target"]),
+    ("U", &["rd", "imm20"]),
+    ("J", &["rd", "target"]),
+];
+
+const REGISTER_FILES: [(&str, &str); 3] = [("x", "X"), ("f", "F"), ("v", "V")];
+
+const ABI_NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4",
+    "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+    "t5", "t6",
+];
+
+// (name, OperandField, least bit, width) of each field
+const FIELDS: [(&str, &str, u32, u32); 3] = [
+    ("rd", "Register", 7, 5),
+    ("rs1", "Register", 15, 5),
+    ("rs2", "Register", 20, 5),
+];
+
+const COMPRESSED_FIELDS: [(&str, &str, u32, u32); 6] = [
+    ("rd", "Register", 7, 5),
+    ("rs1", "Register", 7, 5),
+    ("rs2", "Register", 2, 5),
+    ("rd'", "Compact", 2, 3),
+    ("rs2'", "Compact", 2, 3),
+    ("rs1'", "Compact", 7, 3),
+];
+
+// Overlaps are checked word by word, up to 2^OVERLAP_BITS words each
+const OVERLAP_BITS: usize = 16;
+
+fn lookup<T: Copy>(table: &[(&str, T)], key: &str) -> Option<T> {
+    table
+        .iter()
+        .find(|(name, _)| *name == key)
+        .map(|(_, value)| *value)
+}
+
+fn rust_str(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn xlen_name(xlen: Option<u32>) -> &'static str {
+    match xlen {
+        None => "Xlen::Both",
+        Some(32) => "Xlen::Rv32",
+        _ => "Xlen::Rv64",
+    }
+}
+
+fn parse_hex(value: &str) -> Option<u32> {
+    u32::from_str_radix(value.trim_start_matches("0x"), 16).ok()
+}
+
+#[derive(Debug, Clone)]
+pub struct Instruction {
+    name: String,
+    // mask and match bits as the template spells them, the generated code
+    // repeats them verbatim
+    mask: String,
+    match_bits: String,
+    handler: String,
+    opcode_name: String,
+    fmt: String,
+    xlen: Option<u32>,
+    min_version: u32,
+    ext: String,
+    asm: String,
+    // (mask, min_version) of merged entries, see merge_duplicate_keys
+    versions: Vec<(String, u32)>,
+    reads: Vec<String>,
+    writes: Vec<String>,
+}
+
+impl Instruction {
+    fn mask_value(&self) -> u32 {
+        parse_hex(&self.mask).unwrap()
+    }
+
+    fn match_value(&self) -> u32 {
+        parse_hex(&self.match_bits).unwrap()
+    }
+
+    fn matches(&self, word: u32) -> bool {
+        word & self.mask_value() == self.match_value()
+    }
+
+    fn supports(&self, xlen: u32) -> bool {
+        self.xlen.is_none_or(|own| own == xlen)
+    }
+
+    fn to_rust_construct_inst(&self) -> String {
+        let mut code = format!(
+            "const INST_{} : InstructionInfo = InstructionInfo::new({},{},insts::OP_{},{})",
+            self.name, self.mask, self.match_bits, self.opcode_name, self.handler
+        );
+        if self.xlen.is_some() {
+            code += &format!(".with_xlen({})", xlen_name(self.xlen));
+        }
+        if self.min_version != 0 {
+            code += &format!(".with_min_version({})", self.min_version);
+        }
+        if !self.versions.is_empty() {
+            let versions: Vec<String> = self
+                .versions
+                .iter()
+                .map(|(mask, version)| format!("({}, {})", mask, version))
+                .collect();
+            code += &format!(".with_versions(&[{}])", versions.join(", "));
+        }
+        code + ";\n"
+    }
+
+    // r_builder is undone by r_encoder, caddi_cnop by caddi_cnop_encoder
+    fn encoder(&self) -> String {
+        let handler = self
+            .handler
+            .strip_suffix("_builder")
+            .unwrap_or(&self.handler);
+        format!("{}_encoder", handler)
+    }
+
+    fn to_rust_meta(&self) -> String {
+        format!(
+            "InstructionMeta {{ name: {}, opcode: insts::OP_{}, mask: {}, match_bits: {}, \
+             format: {}, xlen: {}, min_version: {}, extension: {}, asm: {}, encoder: {}, \
+             reads: &[{}], writes: &[{}] }},\n",
+            rust_str(&self.name),
+            self.opcode_name,
+            self.mask,
+            self.match_bits,
+            rust_str(&self.fmt),
+            xlen_name(self.xlen),
+            self.min_version,
+            rust_str(&self.ext),
+            rust_str(&self.asm),
+            self.encoder(),
+            self.reads.join(", "),
+            self.writes.join(", ")
+        )
+    }
+}
+
+// Operand layout in spec notation, e.g. j_immediate from
+//     imm[11|4|9:8|10|6|7|3:1|5]=[12:2]
+// with one `name[imm bits]=[instruction bits]` part per instruction field.
+// Immediates named *uimm* are unsigned, the rest are sign extended from
+// their highest bit.
+pub struct Layout {
+    name: String,
+    spec: String,
+    signed: bool,
+    // (lower, length, shifts) of each run of bits contiguous in both
+    runs: Vec<(u32, u32, u32)>,
+    mask: u32,
+}
+
+// `hi:lo` or `bit`, highest bit first
+fn parse_bits(bits: &str) -> Option<Vec<u32>> {
+    match bits.split_once(':') {
+        Some((hi, lo)) => {
+            let (hi, lo): (u32, u32) = (hi.parse().ok()?, lo.parse().ok()?);
+            if hi < lo {
+                return None;
+            }
+            Some((lo..=hi).rev().collect())
+        }
+        None => Some(vec![bits.parse().ok()?]),
+    }
+}
+
+// (name, instruction bits, immediate bits) of one `name[imm bits]=[bits]`
+fn parse_layout_part(part: &str) -> Option<(&str, Vec<u32>, Vec<u32>)> {
+    let (imm, field) = part.split_once('=')?;
+    let (imm_name, imm_bits) = imm.strip_suffix(']')?.split_once('[')?;
+    let field = parse_bits(field.strip_prefix('[')?.strip_suffix(']')?)?;
+    let mut bits = Vec::new();
+    for part in imm_bits.split('|') {
+        bits.extend(parse_bits(part)?);
+    }
+    Some((imm_name, field, bits))
+}
+
+impl Layout {
+    fn new(name: &str, spec: &str) -> Self {
+        let mut signed = None;
+        // (instruction bit, immediate bit) pairs
+        let mut pairs: Vec<(u32, u32)> = Vec::new();
+        for part in spec.split_whitespace() {
+            let (imm_name, field, imm_bits) = parse_layout_part(part)
+                .unwrap_or_else(|| panic!("Error: layout {}: cannot parse {}", name, part));
+            if field.len() != imm_bits.len() {
+                panic!(
+                    "Error: layout {}: {} maps {} bits to {}",
+                    name,
+                    part,
+                    imm_bits.len(),
+                    field.len()
+                );
+            }
+            let part_signed = !imm_name.contains("uimm");
+            if signed.is_some_and(|signed| signed != part_signed) {
+                panic!("Error: layout {}: mixes signed and unsigned parts", name);
+            }
+            signed = Some(part_signed);
+            pairs.extend(field.into_iter().zip(imm_bits));
+        }
+        for (i, what) in [(0, "instruction"), (1, "immediate")] {
+            let mut bits: Vec<u32> = pairs.iter().map(|pair| [pair.0, pair.1][i]).collect();
+            bits.sort_unstable();
+            bits.dedup();
+            if bits.len() != pairs.len() {
+                panic!("Error: layout {}: {} bits are used twice", name, what);
+            }
+        }
+        // merge bits that stay contiguous in both into (lower, length, shifts)
+        pairs.sort_unstable();
+        let mut runs: Vec<(u32, u32, u32)> = Vec::new();
+        for (bit, imm_bit) in pairs {
+            if let Some((lower, length, shifts)) = runs.last_mut() {
+                if bit == *lower + *length && imm_bit == *shifts + *length {
+                    *length += 1;
+                    continue;
+                }
+            }
+            runs.push((bit, 1, imm_bit));
+        }
+        runs.sort_by_key(|run| run.2);
+        let mask = runs
+            .iter()
+            .map(|(lower, length, _)| ((1u32 << length) - 1) << lower)
+            .sum();
+        Layout {
+            name: name.to_string(),
+            spec: spec.to_string(),
+            signed: signed.unwrap_or(true),
+            runs,
+            mask,
+        }
+    }
+
+    fn to_rust(&self) -> String {
+        let (imm_type, imm) = if self.signed {
+            ("i32", "imm as u32")
+        } else {
+            ("u32", "imm")
+        };
+        let mut extract = Vec::new();
+        let mut encode = Vec::new();
+        for (i, (lower, length, shifts)) in self.runs.iter().enumerate() {
+            let x = if self.signed && i == self.runs.len() - 1 {
+                "xs"
+            } else {
+                "x"
+            };
+            extract.push(format!(
+                "{}(instruction_bits, {}, {}, {})",
+                x, lower, length, shifts
+            ));
+            encode.push(format!("x({}, {}, {}, {})", imm, shifts, length, lower));
+        }
+        let mut extract = extract.join(" | ");
+        if self.signed {
+            extract = format!("({}) as i32", extract);
+        }
+        format!(
+            r#"
+// {spec}
+#[inline(always)]
+pub fn {name}(instruction_bits: u32) -> {imm_type} {{
+    {extract}
+}}
+
+// Inverse of {name}, immediate bits outside the layout are dropped
+#[inline(always)]
+pub fn encode_{name}(imm: {imm_type}) -> u32 {{
+    {encode}
+}}
+"#,
+            spec = self.spec,
+            name = self.name,
+            imm_type = imm_type,
+            extract = extract,
+            encode = encode.join(" | ")
+        )
+    }
+
+    fn to_rust_test(&self) -> String {
+        format!(
+            r#"
+#[test]
+pub fn test_layout_{name}() {{
+    let mut instruction_bits = 0u32;
+    loop {{
+        assert_eq!(encode_{name}({name}(instruction_bits)), instruction_bits);
+        instruction_bits = instruction_bits.wrapping_sub({mask:#x}) & {mask:#x};
+        if instruction_bits == 0 {{
+            break;
+        }}
+    }}
+}}
+"#,
+            name = self.name,
+            mask = self.mask
+        )
+    }
+}
+
+// Operands in the reads=/writes= notation, space separated `file:field` with
+// file one of x, f and v. Fields are rd, rs1 and rs2, the rd', rs1' and rs2'
+// of compressed formats, mask for v0 under vm=0, or the ABI name of an x
+// register the instruction implies. Fields the mask fixes are left out.
+fn parse_operands(inst: &Instruction, text: &str, compressed: bool) -> Vec<String> {
+    let fields: &[(&str, &str, u32, u32)] = if compressed {
+        &COMPRESSED_FIELDS
+    } else {
+        &FIELDS
+    };
+    let mut operands: Vec<String> = Vec::new();
+    for token in text.split_whitespace() {
+        let (file, name) = token.split_once(':').unwrap_or((token, ""));
+        let register_file = lookup(&REGISTER_FILES, file).unwrap_or_else(|| {
+            panic!(
+                "Error: {}: operand {} is not in x, f or v",
+                inst.name, token
+            )
+        });
+        let field = if let Some((_, field, lower, length)) =
+            fields.iter().find(|(field, _, _, _)| *field == name)
+        {
+            let bits = ((1u32 << length) - 1) << lower;
+            if inst.mask_value() & bits == bits {
+                continue;
+            }
+            format!("{}({})", field, lower)
+        } else if name == "mask" && file == "v" {
+            "Mask".to_string()
+        } else if let Some(index) = ABI_NAMES.iter().position(|abi| *abi == name) {
+            if file != "x" {
+                panic!("Error: {}: unknown operand {}", inst.name, token);
+            }
+            format!("Fixed({})", index)
+        } else {
+            panic!("Error: {}: unknown operand {}", inst.name, token);
+        };
+        let operand = format!(
+            "Operand {{ file: RegisterFile::{}, field: OperandField::{} }}",
+            register_file, field
+        );
+        if !operands.contains(&operand) {
+            operands.push(operand);
+        }
+    }
+    operands
+}
+
+// Splits a template line at commas like a csv reader, a column starting
+// with a quote runs to the closing one, e.g. "asm=rd,rs1,rs2"
+fn split_columns(line: &str) -> Vec<String> {
+    let mut columns = Vec::new();
+    let mut column = String::new();
+    let (mut start, mut quoted) = (true, false);
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' if start => continue,
+            '"' if start => quoted = true,
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                column.push(c);
+            }
+            '"' if quoted => quoted = false,
+            ',' if !quoted => {
+                columns.push(column.trim().to_string());
+                column.clear();
+                start = true;
+                continue;
+            }
+            c => column.push(c),
+        }
+        start = false;
+    }
+    columns.push(column.trim().to_string());
+    columns
+}
+
+pub struct Template {
+    pub basename: String,
+    set_instruction_length: String,
+    instructions: Vec<Instruction>,
+    layouts: Vec<Layout>,
+}
+
+impl Template {
+    pub fn parse(path: &Path) -> Self {
+        let filename = path.display();
+        let source = fs::read_to_string(path)
+            .unwrap_or_else(|_| panic!("Error: Could not open {} for reading.", filename));
+        let basename = path
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .split('.')
+            .next()
+            .unwrap()
+            .to_string();
+        let default_ext = match basename.strip_prefix("rv") {
+            Some(ext) => ext.to_uppercase(),
+            None => basename.clone(),
+        };
+        let mut lines = source.lines();
+        let set_instruction_length = split_columns(lines.next().unwrap_or(""))[0].clone();
+        let compressed = set_instruction_length.ends_with("_2");
+        let mut instructions = Vec::new();
+        let mut layouts = Vec::new();
+        for line in lines {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let line = split_columns(line);
+            if line[0].starts_with('#') {
+                continue;
+            }
+            if line[0] == "layout" {
+                if line.len() != 3 {
+                    panic!("Error: {}: layout needs a name and a spec", filename);
+                }
+                layouts.push(Layout::new(&line[1], &line[2]));
+                continue;
+            }
+            let named: Vec<(&str, &str)> = line
+                .iter()
+                .filter_map(|column| column.split_once('='))
+                .collect();
+            let get = |key: &str| named.iter().rev().find(|(k, _)| *k == key).map(|(_, v)| *v);
+            let line: Vec<&String> = line.iter().filter(|column| !column.contains('=')).collect();
+            if line.len() < 4 {
+                panic!(
+                    "Error: {}: {} needs mask, match and handler",
+                    filename, line[0]
+                );
+            }
+            let name = line[0].to_string();
+            let number = |value: &str| {
+                parse_hex(value).unwrap_or_else(|| {
+                    panic!("Error: {}: {} has bad bits {}", filename, name, value)
+                });
+                value.to_string()
+            };
+            let mut inst = Instruction {
+                mask: number(line[1]),
+                match_bits: number(line[2]),
+                handler: line[3].to_string(),
+                opcode_name: line
+                    .get(4)
+                    .map_or(name.clone(), |opcode| opcode.to_string()),
+                name: name.clone(),
+                fmt: String::new(),
+                xlen: None,
+                min_version: 0,
+                ext: String::new(),
+                asm: String::new(),
+                versions: Vec::new(),
+                reads: Vec::new(),
+                writes: Vec::new(),
+            };
+            for (key, _) in &named {
+                if !COLUMNS.contains(key) {
+                    panic!(
+                        "Error: {}: {} has unknown column {}",
+                        filename, inst.name, key
+                    );
+                }
+            }
+            inst.xlen = match get("xlen") {
+                None => None,
+                Some("32") => Some(32),
+                Some("64") => Some(64),
+                Some(_) => panic!(
+                    "Error: {}: {} has xlen other than 32 or 64",
+                    filename, inst.name
+                ),
+            };
+            inst.fmt = get("fmt").unwrap_or("").to_string();
+            inst.min_version = get("min_version").map_or(0, |version| {
+                version.parse().unwrap_or_else(|_| {
+                    panic!("Error: {}: {} has bad min_version", filename, inst.name)
+                })
+            });
+            inst.ext = get("ext").unwrap_or(&default_ext).to_string();
+            inst.asm = get("asm").unwrap_or("").to_string();
+            let asm_fields = lookup(&ASM_FIELDS, &inst.fmt).unwrap_or(&[]);
+            for field in inst
+                .asm
+                .split(|c: char| !c.is_ascii_lowercase() && !c.is_ascii_digit())
+                .filter(|field| !field.is_empty())
+            {
+                if !asm_fields.contains(&field) {
+                    panic!(
+                        "Error: {}: {} has {} in asm= for format {}",
+                        filename, inst.name, field, inst.fmt
+                    );
+                }
+            }
+            let defaults = FORMAT_OPERANDS
+                .iter()
+                .find(|(fmt, _, _)| *fmt == inst.fmt)
+                .map(|(_, reads, writes)| (*reads, *writes));
+            if defaults.is_none() && !(get("reads").is_some() && get("writes").is_some()) {
+                panic!(
+                    "Error: {}: {} needs reads= and writes= for format {}",
+                    filename, inst.name, inst.fmt
+                );
+            }
+            let (reads, writes) = defaults.unwrap_or(("", ""));
+            inst.reads = parse_operands(&inst, get("reads").unwrap_or(reads), compressed);
+            inst.writes = parse_operands(&inst, get("writes").unwrap_or(writes), compressed);
+            instructions.push(inst);
+        }
+        Template {
+            basename,
+            set_instruction_length,
+            instructions,
+            layouts,
+        }
+    }
+
+    // The keys of the hash lookup, one per merged entry
+    pub fn keys(&self) -> Vec<u32> {
+        merge_duplicate_keys(&self.instructions)
+            .iter()
+            .map(|inst| inst.match_value())
+            .collect()
+    }
+
+    // All bits of an instruction word
+    fn word(&self) -> &'static str {
+        if self.set_instruction_length.ends_with("_2") {
+            "0xffff"
+        } else {
+            "0xffffffff"
+        }
+    }
+
+    fn word_mask(&self) -> u32 {
+        if self.set_instruction_length.ends_with("_2") {
+            0xffff
+        } else {
+            0xffff_ffff
+        }
+    }
+
+    // The decoder of module, a perfect hash lookup with hash or a match tree
+    // without
+    pub fn gen_decoder(&self, module: &str, hash: Option<&PerfectHash>, seed: u64) -> String {
+        let filename = format!("templates/{}", self.basename);
+        let insts = merge_duplicate_keys(&self.instructions);
+        let overlaps = self.check_overlaps(&filename, &insts);
+        let basename = &self.basename;
+        let mut code = self.prelude();
+        match hash {
+            Some(hash) => {
+                code += &gen_hashmap_filter(basename, &insts, &overlaps, seed, hash, module);
+                code += &self.interface_template(insts.len());
+            }
+            None => code += &self.gen_tree(&insts, &overlaps),
+        }
+        code += &postlude(&insts, hash.is_none());
+        code += &gen_meta(&self.instructions);
+        code += &self.encode_template();
+        code += &self.layouts.iter().map(Layout::to_rust).collect::<String>();
+        code += &self.gen_test(&insts, hash.is_none());
+        code
+    }
+
+    // Words matching both a and b, the bits neither mask covers enumerated.
+    // None if there are more than 2^OVERLAP_BITS of them.
+    fn overlap_words(&self, a: &Instruction, b: &Instruction) -> Option<Vec<u32>> {
+        let free = self.word_mask() & !(a.mask_value() | b.mask_value());
+        if free.count_ones() as usize > OVERLAP_BITS {
+            return None;
+        }
+        let mut words = Vec::new();
+        let mut bits = 0u32;
+        loop {
+            words.push(a.match_value() | b.match_value() | bits);
+            bits = bits.wrapping_sub(free) & free;
+            if bits == 0 {
+                return Some(words);
+            }
+        }
+    }
+
+    // An overlap is only fine when both entries share a handler, which then
+    // tells the encodings apart (e.g. caddi_cnop for C.ADDI/C.NOP).
+    // Otherwise the entry picked depends on the order of MASKS, so the build
+    // fails. The entry picked also decides xlen and min_version, so every
+    // word of an overlap must be available where the entry with the most
+    // mask bits matching it says, test_overlaps_* then checks the handler
+    // returns its opcode.
+    fn check_overlaps<'a>(
+        &'a self,
+        filename: &str,
+        merged: &[Instruction],
+    ) -> Vec<(&'a Instruction, &'a Instruction)> {
+        let overlaps = find_overlaps(&self.instructions);
+        let unresolved: Vec<String> = overlaps
+            .iter()
+            .filter(|(a, b)| a.handler != b.handler)
+            .map(|(a, b)| {
+                format!(
+                    "{}: {} ({}, {}, {}) overlaps {} ({}, {}, {})",
+                    filename,
+                    a.name,
+                    a.mask,
+                    a.match_bits,
+                    a.handler,
+                    b.name,
+                    b.mask,
+                    b.match_bits,
+                    b.handler
+                )
+            })
+            .collect();
+        if !unresolved.is_empty() {
+            panic!(
+                "{}\nError: overlapping entries must share a handler",
+                unresolved.join("\n")
+            );
+        }
+        for (a, b) in &overlaps {
+            let words = self.overlap_words(a, b).unwrap_or_else(|| {
+                panic!(
+                    "Error: {}: {} overlaps {} on more than 2^{} words, too many to check",
+                    filename, a.name, b.name, OVERLAP_BITS
+                )
+            });
+            for word in words {
+                for xlen in [32, 64] {
+                    let expected = expected_version(&self.instructions, word, xlen);
+                    let found = factory_version(merged, word, xlen);
+                    if found != expected {
+                        panic!(
+                            "Error: {}: {:#x} of {} and {} is decoded from version {:?} \
+                             under RV{} instead of {:?}",
+                            filename, word, a.name, b.name, found, xlen, expected
+                        );
+                    }
+                }
+            }
+        }
+        overlaps
+    }
+
+    fn prelude(&self) -> String {
+        format!(
+            r#"use super::*;
+use super::opcodes as insts;
+use super::operands::{{OperandField, RegisterFile}};
+use ckb_vm::Register;
+use ckb_vm::instructions::{{{}, extract_opcode, Instruction}};
+"#,
+            self.set_instruction_length
+        )
+    }
+
+    fn interface_template(&self, size: usize) -> String {
+        format!(
+            r#"
+pub fn factory<R: Register>(instruction_bits: u32, version: u32) -> Option<Instruction> {{
+    factory_with_config(instruction_bits, &FactoryConfig::new::<R>(version))
+}}
+
+pub fn factory_with_config(instruction_bits: u32, config: &FactoryConfig) -> Option<Instruction> {{
+    for mask in &MASKS {{
+        let match_bits = instruction_bits & mask;
+        let idx = find_{basename}(match_bits);
+        if  idx < {size} && (INSTRUCTION_LIST[idx].mask & instruction_bits) == INSTRUCTION_LIST[idx].match_bits {{
+            if !INSTRUCTION_LIST[idx].available(instruction_bits, config) {{
+                return None;
+            }}
+            if let Some(instruction) = (INSTRUCTION_LIST[idx].builder)(instruction_bits,INSTRUCTION_LIST[idx].opcode, config) {{
+                return Some({set}(instruction));
+            }} else {{
+                return None;
+            }}
+        }}
+    }}
+    return None
+}}
+"#,
+            basename = self.basename,
+            size = size,
+            set = self.set_instruction_length
+        )
+    }
+
+    // Interface of the match tree backend, find_* already checks the full
+    // mask of the entry it returns
+    fn tree_interface_template(&self, size: usize) -> String {
+        format!(
+            r#"
+pub fn factory<R: Register>(instruction_bits: u32, version: u32) -> Option<Instruction> {{
+    factory_with_config(instruction_bits, &FactoryConfig::new::<R>(version))
+}}
+
+pub fn factory_with_config(instruction_bits: u32, config: &FactoryConfig) -> Option<Instruction> {{
+    let idx = find_{basename}(instruction_bits);
+    if idx < {size} {{
+        if !INSTRUCTION_LIST[idx].available(instruction_bits, config) {{
+            return None;
+        }}
+        if let Some(instruction) = (INSTRUCTION_LIST[idx].builder)(instruction_bits,INSTRUCTION_LIST[idx].opcode, config) {{
+            return Some({set}(instruction));
+        }}
+    }}
+    None
+}}
+"#,
+            basename = self.basename,
+            size = size,
+            set = self.set_instruction_length
+        )
+    }
+
+    // Encoding, shared by both backends
+    fn encode_template(&self) -> String {
+        format!(
+            r#"
+pub fn encode<R: Register>(instruction: Instruction, version: u32) -> Option<u32> {{
+    encode_with_config(instruction, &FactoryConfig::new::<R>(version))
+}}
+
+// Only entries of the opcode are tried, and only words decoding back to
+// instruction are kept.
+pub fn encode_with_config(instruction: Instruction, config: &FactoryConfig) -> Option<u32> {{
+    let instruction = {set}(instruction);
+    let opcode = extract_opcode(instruction);
+    INSTRUCTION_META
+        .iter()
+        .filter(|meta| meta.opcode == opcode)
+        .filter_map(|meta| (meta.encoder)(instruction, meta.match_bits))
+        .find(|instruction_bits| factory_with_config(*instruction_bits, config) == Some(instruction))
+}}
+"#,
+            set = self.set_instruction_length
+        )
+    }
+
+    // Decodes random words of every entry and encodes them again with the
+    // encoder of the entry, the one of the xlen with the most mask bits
+    // matching the word. Every entry must round trip at least once. HINT
+    // entries decode to nop, which the nop encoding of another entry stands
+    // for.
+    fn encode_test_template(&self) -> String {
+        format!(
+            r#"
+#[test]
+pub fn test_encode_{basename}() {{
+    // xorshift, so every run sees the same words
+    let mut state = 0x2545f4914f6cdd1du64;
+    let mut random = || {{
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state as u32
+    }};
+    let configs = [
+        FactoryConfig::new::<u32>(1).with_vector_crypto(),
+        FactoryConfig::new::<u64>(1).with_vector_crypto(),
+        FactoryConfig::new::<u64>(1)
+            .with_vector_profile(VectorProfile::V)
+            .with_vector_crypto(),
+    ];
+    let nop = {set}(ckb_vm::instructions::i::nop());
+    let mut exercised = [false; INSTRUCTION_META.len()];
+    for config in configs.iter() {{
+        for meta in INSTRUCTION_META.iter() {{
+            for _ in 0..64 {{
+                let instruction_bits = meta.match_bits | (random() & !meta.mask & {word});
+                let instruction = match factory_with_config(instruction_bits, config) {{
+                    Some(instruction) => instruction,
+                    None => continue,
+                }};
+                let (idx, meta) = INSTRUCTION_META
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, meta)| instruction_bits & meta.mask == meta.match_bits)
+                    .filter(|(_, meta)| meta.xlen.supports(config))
+                    .max_by_key(|(_, meta)| meta.mask.count_ones())
+                    .unwrap();
+                let encoded = if instruction == nop && meta.opcode != extract_opcode(nop) {{
+                    encode_with_config(instruction, config)
+                }} else {{
+                    (meta.encoder)(instruction, meta.match_bits)
+                }};
+                let encoded = encoded.unwrap_or_else(|| {{
+                    panic!("{{:x}} cannot be encoded by {{}}", instruction_bits, meta.name)
+                }});
+                assert_eq!(
+                    factory_with_config(encoded, config),
+                    Some(instruction),
+                    "{{:x}} encoded by {{}}",
+                    instruction_bits,
+                    meta.name
+                );
+                exercised[idx] = true;
+            }}
+        }}
+    }}
+    for (meta, exercised) in INSTRUCTION_META.iter().zip(exercised.iter()) {{
+        assert!(exercised, "{{}} never decoded", meta.name);
+    }}
+}}
+"#,
+            basename = self.basename,
+            set = self.set_instruction_length,
+            word = self.word()
+        )
+    }
+
+    // Every word two entries both match decodes to the opcode of the entry
+    // with the most mask bits of the xlen (nop for HINTs), and only from its
+    // min_version on. check_overlaps keeps the words at most 2^OVERLAP_BITS.
+    fn overlap_test_template(&self) -> String {
+        format!(
+            r#"
+#[test]
+pub fn test_overlaps_{basename}() {{
+    let configs = [
+        FactoryConfig::new::<u32>(0),
+        FactoryConfig::new::<u32>(1),
+        FactoryConfig::new::<u64>(0),
+        FactoryConfig::new::<u64>(1),
+    ];
+    let nop = {set}(ckb_vm::instructions::i::nop());
+    for (i, a) in INSTRUCTION_META.iter().enumerate() {{
+        for b in INSTRUCTION_META[i + 1..].iter() {{
+            if (a.match_bits ^ b.match_bits) & a.mask & b.mask != 0 {{
+                continue;
+            }}
+            let free = !(a.mask | b.mask) & {word};
+            let mut bits = 0u32;
+            loop {{
+                let instruction_bits = a.match_bits | b.match_bits | bits;
+                for config in configs.iter() {{
+                    let instruction = match factory_with_config(instruction_bits, config) {{
+                        Some(instruction) => instruction,
+                        None => continue,
+                    }};
+                    let meta = INSTRUCTION_META
+                        .iter()
+                        .filter(|meta| instruction_bits & meta.mask == meta.match_bits)
+                        .filter(|meta| meta.xlen.supports(config))
+                        .max_by_key(|meta| (meta.mask.count_ones(), std::cmp::Reverse(meta.min_version)))
+                        .unwrap();
+                    assert!(
+                        config.version >= meta.min_version,
+                        "{{:x}} decoded before version {{}} of {{}}",
+                        instruction_bits,
+                        meta.min_version,
+                        meta.name
+                    );
+                    if instruction != nop || meta.opcode == extract_opcode(nop) {{
+                        assert_eq!(
+                            extract_opcode(instruction),
+                            meta.opcode,
+                            "{{:x}} is not {{}}",
+                            instruction_bits,
+                            meta.name
+                        );
+                    }}
+                }}
+                // next subset of the free bits
+                bits = bits.wrapping_sub(free) & free;
+                if bits == 0 {{
+                    break;
+                }}
+            }}
+        }}
+    }}
+}}
+"#,
+            basename = self.basename,
+            set = self.set_instruction_length,
+            word = self.word()
+        )
+    }
+
+    fn gen_test(&self, insts: &[Instruction], tree: bool) -> String {
+        let mut keys = String::from("[");
+        for inst in insts {
+            keys += &format!("{},\n", inst.match_bits);
+        }
+        keys += "];";
+        // layout and encoder tests go into the same test module
+        let mut extra_tests: String = self.layouts.iter().map(Layout::to_rust_test).collect();
+        extra_tests += &self.encode_test_template();
+        extra_tests += &self.overlap_test_template();
+        let basename = &self.basename;
+        let upper = basename.to_uppercase();
+        let size = insts.len();
+        if tree {
+            // The factory tries MASKS in order, so among overlapping entries
+            // the one with the earliest mask wins. The tree has to agree.
+            return format!(
+                r#"
+
+#[cfg(test)]
+mod tests {{
+use super::*;
+{masks}const K_{upper} : [u32;{size}] = {keys}
+#[test]
+pub fn test_{basename}() {{
+    for i in 0..{size} {{
+        let expected = MASKS.iter().find_map(|mask| {{
+            INSTRUCTION_LIST.iter().position(|x| {{
+                x.mask == *mask && (x.mask & K_{upper}[i]) == x.match_bits
+            }})
+        }});
+        assert_eq!(Some(find_{basename}(K_{upper}[i])), expected)
+    }}
+}}
+{extra_tests}}}
+"#,
+                masks = gen_masks(insts),
+                upper = upper,
+                size = size,
+                keys = keys,
+                basename = basename,
+                extra_tests = extra_tests
+            );
+        }
+        format!(
+            r#"
+
+#[cfg(test)]
+mod tests {{
+use super::*;
+const K_{upper} : [u32;{size}] = {keys}
+#[test]
+pub fn test_{basename}() {{
+    for i in 0..{size} {{
+        assert!(find_{basename}(K_{upper}[i]) == i)
+    }}
+}}
+{extra_tests}}}
+"#,
+            upper = upper,
+            size = size,
+            keys = keys,
+            basename = basename,
+            extra_tests = extra_tests
+        )
+    }
+
+    fn gen_tree(&self, insts: &[Instruction], overlaps: &[(&Instruction, &Instruction)]) -> String {
+        let sorted_masks = get_sorted_masks(insts);
+        let mut entries: Vec<(usize, &Instruction)> = insts.iter().enumerate().collect();
+        entries.sort_by_key(|(idx, inst)| {
+            (
+                sorted_masks.iter().position(|mask| *mask == inst.mask),
+                *idx,
+            )
+        });
+        let (mask_info, overlap_info) = gen_info(insts, overlaps);
+        let tree = gen_tree_node(&entries, 0, insts.len(), 1);
+        let mut code = format!(
+            r#"
+// --- ------------------------------------------- ---
+// --- Following code is generated as a match tree ---
+// --- Masks info (mask, elements):                ---
+{mask_info}// --- Overlaps resolved by handler:                ---
+{overlap_info}// --- ------------------------------------------- ---
+#[inline(always)]
+fn find_{basename}(instruction_bits: u32) -> usize {{
+{tree}}}
+"#,
+            mask_info = mask_info,
+            overlap_info = overlap_info,
+            basename = self.basename,
+            tree = tree
+        );
+        code += &self.tree_interface_template(insts.len());
+        code
+    }
+}
+
+// Every pair of entries that can match the same instruction word, i.e. they
+// agree on all the bits both masks cover. This includes entries with the same
+// match bits, which merge_duplicate_keys merges later on.
+fn find_overlaps(instructions: &[Instruction]) -> Vec<(&Instruction, &Instruction)> {
+    let mut overlaps = Vec::new();
+    for (i, a) in instructions.iter().enumerate() {
+        for b in &instructions[i + 1..] {
+            if (a.match_value() ^ b.match_value()) & a.mask_value() & b.mask_value() == 0 {
+                overlaps.push((a, b));
+            }
+        }
+    }
+    overlaps
+}
+
+// The min_version under xlen the template gives word, that of the matching
+// entry with the most mask bits, ties going to the lower version. None if no
+// entry of xlen matches.
+fn expected_version(instructions: &[Instruction], word: u32, xlen: u32) -> Option<u32> {
+    instructions
+        .iter()
+        .filter(|inst| inst.matches(word) && inst.supports(xlen))
+        .max_by_key(|inst| {
+            (
+                inst.mask_value().count_ones(),
+                std::cmp::Reverse(inst.min_version),
+            )
+        })
+        .map(|inst| inst.min_version)
+}
+
+// The min_version under xlen the factory gives word, by the merged entry it
+// finds first trying MASKS in order, see InstructionInfo::available
+fn factory_version(merged: &[Instruction], word: u32, xlen: u32) -> Option<u32> {
+    let inst = get_sorted_masks(merged).into_iter().find_map(|mask| {
+        let key = word & parse_hex(&mask).unwrap();
+        merged
+            .iter()
+            .rev()
+            .find(|inst| inst.match_value() == key)
+            .filter(|inst| inst.matches(word))
+    })?;
+    if !inst.supports(xlen) {
+        return None;
+    }
+    let version = inst
+        .versions
+        .iter()
+        .map(|(mask, version)| (parse_hex(mask).unwrap(), *version))
+        .filter(|(mask, _)| word & mask == inst.match_value())
+        .max_by_key(|(mask, version)| (mask.count_ones(), std::cmp::Reverse(*version)))
+        .map_or(inst.min_version, |(_, version)| version);
+    Some(version)
+}
+
+// Entries sharing match bits are merged into one with the common mask, which
+// only works if their handler tells them apart, so they must share it. The
+// merged entry is available wherever the most specific of them matching a
+// word is.
+fn merge_duplicate_keys(instructions: &[Instruction]) -> Vec<Instruction> {
+    let mut by_keys: Vec<Vec<&Instruction>> = Vec::new();
+    for inst in instructions {
+        match by_keys
+            .iter_mut()
+            .find(|insts| insts[0].match_bits == inst.match_bits)
+        {
+            Some(insts) => insts.push(inst),
+            None => by_keys.push(vec![inst]),
+        }
+    }
+    let mut merged_insts = Vec::new();
+    for insts in by_keys {
+        let mut merged = insts[0].clone();
+        if insts.len() > 1 {
+            if insts.iter().any(|inst| inst.handler != insts[0].handler) {
+                panic!(
+                    "Error: Duplicate key {} must have the same handler",
+                    insts[0].match_bits
+                );
+            }
+            let mask = insts
+                .iter()
+                .fold(u32::MAX, |mask, inst| mask & inst.mask_value());
+            merged.mask = format!("{:#x}", mask);
+            if insts.iter().any(|inst| inst.xlen != insts[0].xlen) {
+                merged.xlen = None;
+            }
+            merged.min_version = insts.iter().map(|inst| inst.min_version).min().unwrap();
+            // e.g. the C.ADD HINTs, decoded from a later version than C.ADD
+            if insts
+                .iter()
+                .any(|inst| inst.min_version != insts[0].min_version)
+            {
+                merged.versions = insts
+                    .iter()
+                    .map(|inst| (inst.mask.clone(), inst.min_version))
+                    .collect();
+            }
+        }
+        merged_insts.push(merged);
+    }
+    merged_insts
+}
+
+// Masks to try, those with the most entries first
+fn get_sorted_masks(instructions: &[Instruction]) -> Vec<String> {
+    let mut by_masks: Vec<(String, usize)> = Vec::new();
+    for inst in instructions {
+        match by_masks.iter_mut().find(|(mask, _)| *mask == inst.mask) {
+            Some((_, count)) => *count += 1,
+            None => by_masks.push((inst.mask.clone(), 1)),
+        }
+    }
+    by_masks.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    by_masks.into_iter().map(|(mask, _)| mask).collect()
+}
+
+fn gen_masks(instructions: &[Instruction]) -> String {
+    let sorted_masks = get_sorted_masks(instructions);
+    let mut code = format!("const MASKS : [u32; {}] =[\n", sorted_masks.len());
+    for mask in sorted_masks {
+        code += &format!("{},\n", mask);
+    }
+    code + "];\n"
+}
+
+// Header comment lines listing the masks and the resolved overlaps
+fn gen_info(
+    instructions: &[Instruction],
+    overlaps: &[(&Instruction, &Instruction)],
+) -> (String, String) {
+    let mut mask_info = String::new();
+    for mask in get_sorted_masks(instructions) {
+        let count = instructions.iter().filter(|inst| inst.mask == mask).count();
+        mask_info += &format!("// ({}, {})\n", mask, count);
+    }
+    let mut overlap_info = String::new();
+    for (a, b) in overlaps {
+        overlap_info += &format!("// ({}, {}, {})\n", a.name, b.name, a.handler);
+    }
+    (mask_info, overlap_info)
+}
+
+// Instruction definitions and lists
+fn postlude(instructions: &[Instruction], tree: bool) -> String {
+    let mut code: String = instructions
+        .iter()
+        .map(Instruction::to_rust_construct_inst)
+        .collect();
+    code += &format!(
+        "pub const INSTRUCTION_LIST : [InstructionInfo; {}] = [\n",
+        instructions.len()
+    );
+    for inst in instructions {
+        code += &format!("INST_{},\n", inst.name);
+    }
+    code += "];\n";
+    if !tree {
+        code += &gen_masks(instructions);
+    }
+    code
+}
+
+// Metadata of every template line, merged duplicates included
+fn gen_meta(instructions: &[Instruction]) -> String {
+    let mut code = format!(
+        "pub const INSTRUCTION_META : [InstructionMeta; {}] = [\n",
+        instructions.len()
+    );
+    for inst in instructions {
+        code += &inst.to_rust_meta();
+    }
+    code + "];\n"
+}
+
+fn g_type(ng: usize) -> (&'static str, usize) {
+    if ng <= 1 << 8 {
+        ("u8", 1)
+    } else if ng <= 1 << 16 {
+        ("u16", 2)
+    } else {
+        ("u32", 4)
+    }
+}
+
+// The perfect hash lookup of the keys, G and both hash functions
+fn gen_hashmap_filter(
+    basename: &str,
+    instructions: &[Instruction],
+    overlaps: &[(&Instruction, &Instruction)],
+    seed: u64,
+    hash: &PerfectHash,
+    module: &str,
+) -> String {
+    for (idx, inst) in instructions.iter().enumerate() {
+        if hash.index(inst.match_value()) != idx {
+            panic!(
+                "Error: the table of {} does not map key {} to {}",
+                module, inst.match_bits, idx
+            );
+        }
+    }
+    let (mask_info, overlap_info) = gen_info(instructions, overlaps);
+    let ng = hash.ng();
+    let (gtype, gsize) = g_type(ng);
+    let upper = basename.to_uppercase();
+    let pow2 = hash.reduction == Reduction::Pow2;
+    let g: Vec<String> = hash.g.iter().map(u32::to_string).collect();
+    let mut code = format!(
+        r#"
+// --- ------------------------------------------- ---
+// --- Following code is generated by perfect_hash ---
+// --- Seed: {seed}
+// --- Table: NG = {ng}{pow2}, G = {ng} x {gtype} = {gbytes} bytes
+// --- Masks info (mask, elements):                ---
+{mask_info}// --- Overlaps resolved by handler:                ---
+{overlap_info}// --- ------------------------------------------- ---
+const G_{upper} : [{gtype};{ng}] = [{g}];
+"#,
+        seed = seed,
+        ng = ng,
+        pow2 = if pow2 { " (power of two)" } else { "" },
+        gtype = gtype,
+        gbytes = gsize * ng,
+        mask_info = mask_info,
+        overlap_info = overlap_info,
+        upper = upper,
+        g = g.join(", ")
+    );
+    for (version, salt) in hash.salts.iter().enumerate() {
+        code += &match hash.reduction {
+            Reduction::Modulo => format!(
+                r#"
+#[inline(always)]
+fn hash_f_{basename}_{version}(key: u32) -> usize {{
+    return (((key & 0xffff )+1) * {s0} +
+            ((key >> 16 & 0xffff) + 1) * {s1}) as usize;
+}}
+    "#,
+                basename = basename,
+                version = version + 1,
+                s0 = salt[0],
+                s1 = salt[1]
+            ),
+            Reduction::Pow2 => format!(
+                r#"
+#[inline(always)]
+fn hash_f_{basename}_{version}(key: u32) -> usize {{
+    return (((key & 0xffff) + 1).wrapping_mul({s0})
+        .wrapping_add(((key >> 16 & 0xffff) + 1).wrapping_mul({s1})) >> {shift}) as usize;
+}}
+    "#,
+                basename = basename,
+                version = version + 1,
+                s0 = salt[0],
+                s1 = salt[1],
+                shift = hash.shift()
+            ),
+        };
+    }
+    let reduce = if pow2 {
+        format!("& {}", ng - 1)
+    } else {
+        format!("% {}", ng)
+    };
+    code += &format!(
+        r#"
+#[inline(always)]
+fn find_{basename}(key:u32) -> usize {{
+    return ((G_{upper}[hash_f_{basename}_1(key) {reduce}] as usize
+        + G_{upper}[hash_f_{basename}_2(key) {reduce}] as usize) {reduce}) as usize;
+}}
+"#,
+        basename = basename,
+        upper = upper,
+        reduce = reduce
+    );
+    code
+}
+
+// Emit a decision tree as nested matches: split on the bits all remaining
+// entries have in their masks and that are not tested yet, e.g. the major
+// opcode, then funct3, and so on. Entries that no such bit tells apart
+// (overlapping ones, or masks covering disjoint bits) are checked one by one
+// in MASKS order. entries are (index, instruction) in that order.
+fn gen_tree_node(
+    entries: &[(usize, &Instruction)],
+    tested: u32,
+    size: usize,
+    depth: usize,
+) -> String {
+    let pad = "    ".repeat(depth);
+    let common = entries
+        .iter()
+        .fold(u32::MAX, |mask, (_, inst)| mask & inst.mask_value())
+        & !tested;
+    if entries.len() > 1 && common != 0 {
+        let mut arms: BTreeMap<u32, Vec<(usize, &Instruction)>> = BTreeMap::new();
+        for (idx, inst) in entries {
+            arms.entry(inst.match_value() & common)
+                .or_default()
+                .push((*idx, inst));
+        }
+        if arms.len() == 1 {
+            return gen_tree_node(entries, tested | common, size, depth);
+        }
+        let mut code = format!("{}match instruction_bits & {:#x} {{\n", pad, common);
+        for (value, arm_entries) in &arms {
+            let arm = gen_tree_node(arm_entries, tested | common, size, depth + 2);
+            if arm.matches('\n').count() == 1 {
+                code += &format!("{}    {:#x} => {},\n", pad, value, arm.trim());
+            } else {
+                code += &format!("{}    {:#x} => {{\n{}{}    }}\n", pad, value, arm, pad);
+            }
+        }
+        code += &format!("{}    _ => {},\n", pad, size);
+        code += &format!("{}}}\n", pad);
+        return code;
+    }
+    let mut code = String::new();
+    for (idx, inst) in entries {
+        let untested = inst.mask_value() & !tested;
+        if untested == 0 {
+            return code + &format!("{}{}\n", pad, idx);
+        }
+        code += &format!(
+            "{pad}if instruction_bits & {:#x} == {:#x} {{\n{pad}    return {};\n{pad}}}\n",
+            untested,
+            inst.match_value() & untested,
+            idx,
+            pad = pad
+        );
+    }
+    code + &format!("{}{}\n", pad, size)
+}
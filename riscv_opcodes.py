@@ -0,0 +1,229 @@
+#!/usr/bin/env python
+
+import sys
+import os.path
+from optparse import OptionParser
+
+"""
+Builder, format and assembly syntax picked from the operand fields of an
+instruction, fields are compared as a set.
+"""
+BUILDERS = {
+    frozenset(["rd", "rs1", "rs2"]): ("r_builder", "R", "rd,rs1,rs2"),
+    frozenset(["rd", "rs1"]): ("r_builder", "R", "rd,rs1"),
+    frozenset(["rd", "rs1", "imm12"]): ("is_builder", "I", "rd,rs1,imm"),
+    frozenset(["imm12hi", "rs1", "rs2", "imm12lo"]): ("ss_builder", "S", "rs2,imm(rs1)"),
+    frozenset(["bimm12hi", "rs1", "rs2", "bimm12lo"]): ("sb_builder", "B", "rs1,rs2,target"),
+    frozenset(["rd", "imm20"]): ("us_builder", "U", "rd,imm20"),
+    frozenset(["rd", "jimm20"]): ("uj_builder", "J", "rd,target"),
+    frozenset(["fm", "pred", "succ", "rs1", "rd"]): ("fence_builder", "I", "pred,succ"),
+    frozenset([]): ("blank_inst_builder", "I", ""),
+}
+
+"""
+Shift immediates: the base ISA masks shamt with the register width, while
+the bit manipulation extensions keep the raw field.
+"""
+SHAMT_BUILDERS = {
+    "I": {"shamtd": "is_alu_builder", "shamtw": "is_1f_builder"},
+    None: {"shamtd": "r64_imm_builder", "shamtw": "roriw_builder"},
+}
+
+# Major opcodes whose imm12 is an offset from rs1: loads and jalr
+OFFSET_OPCODES = [0x03, 0x67]
+
+# Instructions whose fields do not tell the builder
+OVERRIDES = {
+    # ckb-vm ignores the fields of fence.i
+    "fence.i": ("blank_inst_builder", "I", ""),
+}
+
+"""
+Register operands the fields do not tell, as the reads= and writes= columns
+of the templates. fence keeps rd and rs1 as reserved fields, and ecall follows
+the ckb-vm syscall convention: the number in a7, arguments in a0 - a5 and
+the result in a0.
+"""
+OPERANDS = {
+    "fence": ("", ""),
+    "fence.i": ("", ""),
+    "ecall": ("x:a0 x:a1 x:a2 x:a3 x:a4 x:a5 x:a7", "x:a0"),
+}
+
+class Instruction(object):
+    def __init__(self, name, mask, match_bits, fields, ext, xlen):
+        self.name = name
+        self.mask = mask
+        self.match_bits = match_bits
+        self.fields = fields
+        self.ext = ext
+        self.xlen = xlen
+
+    def template_name(self):
+        return self.name.upper().replace(".", "")
+
+    def builder(self):
+        if self.name in OVERRIDES:
+            return OVERRIDES[self.name]
+        shamts = [x for x in self.fields if x.startswith("shamt")]
+        if shamts:
+            builders = SHAMT_BUILDERS.get(self.ext, SHAMT_BUILDERS[None])
+            return (builders[shamts[0]], "I", "rd,rs1,shamt")
+        key = frozenset(self.fields)
+        if key not in BUILDERS:
+            sys.exit(f"Error: no builder for {self.name} with fields {' '.join(self.fields)}")
+        (builder, fmt, asm) = BUILDERS[key]
+        if "imm12" in self.fields and self.match_bits & 0x7f in OFFSET_OPCODES:
+            asm = "rd,imm(rs1)"
+        return (builder, fmt, asm)
+
+"""
+rv_zba -> (Zba, None), rv64_i -> (I, 64), rv32_zbb -> (Zbb, 32)
+"""
+def parse_filename(filename):
+    parts = os.path.basename(filename).split("_", 1)
+    xlen = parts[0][2:] or None
+    ext = parts[1]
+    ext = ext.upper() if len(ext) == 1 else ext.capitalize()
+    return (ext, xlen)
+
+"""
+Encoding of one line: fixed bits are `hi..lo=value` or `bit=value`, every
+other token is an operand field.
+"""
+def parse_encoding(tokens):
+    mask = 0
+    match_bits = 0
+    fields = []
+    for token in tokens:
+        if "=" not in token:
+            fields.append(token)
+            continue
+        (bits, value) = token.split("=")
+        if ".." in bits:
+            (hi, lo) = map(int, bits.split(".."))
+        else:
+            hi = lo = int(bits)
+        field_mask = ((1 << (hi - lo + 1)) - 1) << lo
+        value = int(value, 0) << lo
+        if value & ~field_mask or mask & field_mask:
+            sys.exit(f"Error: bad bit range {token} in {' '.join(tokens)}")
+        mask |= field_mask
+        match_bits |= value
+    return (mask, match_bits, fields)
+
+"""
+Returns the instructions of all files by name, and the $pseudo_op lines
+with the register width of their file.
+$import lines are not followed, pass the imported files explicitly instead.
+"""
+def parse_files(filenames):
+    instructions = dict()
+    pseudo_ops = []
+    for filename in filenames:
+        (ext, xlen) = parse_filename(filename)
+        try:
+            with open(filename) as f:
+                lines = f.read().split("\n")
+        except IOError:
+            sys.exit("Error: Could not open {} for reading.".format(filename))
+        for line in lines:
+            tokens = line.split("#")[0].split()
+            if not tokens:
+                continue
+            if tokens[0] == "$pseudo_op":
+                pseudo_ops.append((tokens[1:], xlen))
+                continue
+            if tokens[0].startswith("$"):
+                print(f"{filename}: ignoring {tokens[0]} {' '.join(tokens[1:2])}", file=sys.stderr)
+                continue
+            (mask, match_bits, fields) = parse_encoding(tokens[1:])
+            if match_bits & 0x3 != 0x3:
+                sys.exit(f"Error: {tokens[0]} is compressed, these need handwritten handlers")
+            if tokens[0] in instructions:
+                sys.exit(f"Error: {tokens[0]} is defined twice")
+            instructions[tokens[0]] = Instruction(tokens[0], mask, match_bits, fields, ext, xlen)
+    return (instructions, pseudo_ops)
+
+"""
+A pseudo op makes an instruction of another width available on this one when
+every encoding of it is also an encoding of the instruction, e.g. the RV32
+slli is the RV64 one with shamt[5]=0.
+"""
+def apply_pseudo_ops(instructions, pseudo_ops):
+    for (tokens, xlen) in pseudo_ops:
+        name = tokens[0].split("::")[1]
+        (mask, match_bits, _) = parse_encoding(tokens[2:])
+        inst = instructions.get(name)
+        if inst is None or inst.xlen == xlen:
+            continue
+        if match_bits & inst.mask == inst.match_bits and mask & inst.mask == inst.mask:
+            inst.xlen = None
+        else:
+            print(f"ignoring RV{xlen} {name}, its encoding differs from RV{inst.xlen}", file=sys.stderr)
+
+def default_ext(outname):
+    basename = os.path.basename(outname).split('.')[0]
+    return basename[2:].upper() if basename.startswith("rv") else basename
+
+def gen_template(instructions, ext, command):
+    rows = []
+    for inst in sorted(instructions.values(), key=lambda x: x.template_name()):
+        (builder, fmt, asm) = inst.builder()
+        named = [f"fmt={fmt}"]
+        if inst.xlen is not None:
+            named.append(f"xlen={inst.xlen}")
+        if inst.ext != ext:
+            named.append(f"ext={inst.ext}")
+        # quoted, as the commas would split the column
+        named.append(f'"asm={asm}"' if "," in asm else f"asm={asm}")
+        if inst.name in OPERANDS:
+            (reads, writes) = OPERANDS[inst.name]
+            named += [f"reads={reads}", f"writes={writes}"]
+        rows.append([inst.template_name(), hex(inst.mask), hex(inst.match_bits), builder] + named)
+    width = [max(len(row[i]) for row in rows) for i in range(4)]
+    code = "set_instruction_length_4\n"
+    code += f"# Generated by: {command}\n"
+    for row in rows:
+        code += " , ".join([row[i].ljust(width[i]) for i in range(4)] + row[4:]) + "\n"
+    return code
+
+def main():
+    usage = "usage: %prog [-o TEMPLATE] RV_FILE..."
+    description = """\
+Convert riscv-opcodes instruction definitions (rv_* files) into a template
+for build/codegen.rs, deriving mask, match and builder from the encoding.
+"""
+
+    parser = OptionParser(usage = usage,
+                          description = description,
+                          prog = sys.argv[0])
+
+    parser.add_option("-o", "--output",
+                      action  = "store",
+                      help    = "Write the template to FILE instead of "
+                                "standard output.",
+                      metavar = "FILE")
+
+    options, args = parser.parse_args()
+    if len(args) == 0:
+        parser.error("Missing input file name")
+
+    (instructions, pseudo_ops) = parse_files(args)
+    apply_pseudo_ops(instructions, pseudo_ops)
+    outname = options.output if options.output else ""
+    command = " ".join([os.path.basename(sys.argv[0])] + sys.argv[1:])
+    code = gen_template(instructions, default_ext(outname), command)
+
+    if options.output:
+        try:
+            stream = open(options.output, 'w')
+        except IOError:
+            sys.exit("Error: Could not open {} for writing.".format(options.output))
+    else:
+        stream = sys.stdout
+    stream.write(code)
+
+
+if __name__ == '__main__':
+    main()
@@ -21,12 +21,12 @@ pub fn r64_imm_builder(
 
 pub fn roriw_builder(
     instruction_bits: u32,
-    _: insts::InstructionOpcode,
+    opcode: insts::InstructionOpcode,
     _: &FactoryConfig,
 ) -> Option<Instruction> {
     Some(
         Itype::new_u(
-            insts::OP_SLLIUW,
+            opcode,
             rd(instruction_bits),
             rs1(instruction_bits),
             x(instruction_bits, 20, 5, 0),
@@ -34,3 +34,34 @@ pub fn roriw_builder(
         .0,
     )
 }
+
+pub fn r64_imm_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = Itype(instruction);
+    Some(match_bits | encode_rd(i.rd()) | encode_rs1(i.rs1()) | x(i.immediate_u(), 0, 6, 20))
+}
+
+pub fn roriw_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = Itype(instruction);
+    Some(match_bits | encode_rd(i.rd()) | encode_rs1(i.rs1()) | x(i.immediate_u(), 0, 5, 20))
+}
+
+#[cfg(all(test, feature = "rvb"))]
+mod tests {
+    use super::*;
+    use ckb_vm::instructions::extract_opcode;
+
+    // roriw a0, a1, 5 and slli.uw a0, a1, 5 from llvm-mc -triple=riscv64
+    // -mattr=+zba,+zbb -show-encoding
+    #[test]
+    fn test_roriw() {
+        let config = FactoryConfig::new::<u64>(1);
+        for (instruction_bits, opcode) in [
+            (0x6055d51b, insts::OP_RORIW),
+            (0x0855951b, insts::OP_SLLIUW),
+        ] {
+            let i = Itype(rvb_decoder::factory_with_config(instruction_bits, &config).unwrap());
+            assert_eq!(extract_opcode(i.0), opcode);
+            assert_eq!((i.rd(), i.rs1(), i.immediate_u()), (10, 11, 5));
+        }
+    }
+}
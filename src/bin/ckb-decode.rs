@@ -0,0 +1,146 @@
+// Disassembles the executable sections of an RV64 ELF file the way ckb-vm
+// decodes them, e.g. with the HINTs of a VM version. With --check only the
+// words the VM would reject under the version and ISA profile are listed,
+// with --stats the instruction mix of the code or of a PC trace. Exits with
+// 1 when a word does not decode or is rejected, with 2 on bad arguments or
+// files.
+use ckb_vm_decoder::compat::{check_elf, IsaProfile, LATEST_VERSION};
+use ckb_vm_decoder::disasm::{disassemble_with_config, DisasmConfig};
+use ckb_vm_decoder::elf::{executable_sections, Section};
+use ckb_vm_decoder::stats::Mix;
+use ckb_vm_decoder::FactoryConfig;
+use std::process::exit;
+
+const USAGE: &str = "usage: ckb-decode [--version <n>] [--no-aliases] [--check [--isa <profile>]] \
+[--stats [--trace <pcs>] [--bench-mix <out>]] <elf>";
+
+fn fail(message: &str) -> ! {
+    eprintln!("ckb-decode: {}", message);
+    exit(2)
+}
+
+fn main() {
+    let mut version = LATEST_VERSION;
+    let mut disasm_config = DisasmConfig::new();
+    let mut check = false;
+    let mut profile = IsaProfile::imc();
+    let mut stats = false;
+    let mut trace = None;
+    let mut bench_mix = None;
+    let mut path = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--version" => {
+                version = args
+                    .next()
+                    .and_then(|version| version.parse().ok())
+                    .unwrap_or_else(|| fail(USAGE))
+            }
+            "--no-aliases" => disasm_config = disasm_config.without_aliases(),
+            "--check" => check = true,
+            "--isa" => {
+                profile = args
+                    .next()
+                    .and_then(|profile| IsaProfile::parse(&profile))
+                    .unwrap_or_else(|| fail(USAGE))
+            }
+            "--stats" => stats = true,
+            "--trace" => trace = Some(args.next().unwrap_or_else(|| fail(USAGE))),
+            "--bench-mix" => bench_mix = Some(args.next().unwrap_or_else(|| fail(USAGE))),
+            _ if path.is_none() && !arg.starts_with("--") => path = Some(arg),
+            _ => fail(USAGE),
+        }
+    }
+    let path = path.unwrap_or_else(|| fail(USAGE));
+    let read = |path: &str| {
+        std::fs::read(path).unwrap_or_else(|error| fail(&format!("{}: {}", path, error)))
+    };
+    let elf_bytes = read(&path);
+    let not_elf = format!("{}: not an RV64 RISC-V ELF file", path);
+
+    if stats {
+        let config = FactoryConfig::new::<u64>(version);
+        let mix = match &trace {
+            Some(trace_path) => {
+                let trace = String::from_utf8_lossy(&read(trace_path)).into_owned();
+                Mix::from_trace(&elf_bytes, &trace, &config)
+                    .unwrap_or_else(|| fail(&format!("{} or {} is malformed", path, trace_path)))
+            }
+            None => Mix::from_elf(&elf_bytes, &config).unwrap_or_else(|| fail(&not_elf)),
+        };
+        print!("{}", mix.report());
+        if let Some(out) = bench_mix {
+            std::fs::write(&out, mix.to_bench_mix())
+                .unwrap_or_else(|error| fail(&format!("{}: {}", out, error)));
+        }
+        return;
+    }
+    if check {
+        let rejections = check_elf(&elf_bytes, version, &profile).unwrap_or_else(|| fail(&not_elf));
+        for rejection in &rejections {
+            // 2 byte words printed as such, like in the disassembly
+            let width = if rejection.instruction_bits & 0x3 != 0x3 {
+                4
+            } else {
+                8
+            };
+            println!(
+                "{:>8x}:\t{:0width$x}\t{}",
+                rejection.pc,
+                rejection.instruction_bits,
+                rejection.reason,
+                width = width
+            );
+        }
+        if !rejections.is_empty() {
+            exit(1);
+        }
+        return;
+    }
+    let sections = executable_sections(&elf_bytes).unwrap_or_else(|| fail(&not_elf));
+    println!("\n{}:     file format elf64-littleriscv", path);
+    if disassemble(&sections, version, &disasm_config) > 0 {
+        exit(1);
+    }
+}
+
+// Prints the sections as objdump -d does, returns the number of words that
+// did not decode
+fn disassemble(sections: &[Section], version: u32, disasm_config: &DisasmConfig) -> usize {
+    let config = FactoryConfig::new::<u64>(version);
+    let mut failures = 0;
+    for section in sections {
+        println!("\n\nDisassembly of section {}:", section.name);
+        for word in section.words(&config) {
+            if let Some(names) = section.symbols.get(&word.pc) {
+                for name in names {
+                    println!("\n{:016x} <{}>:", word.pc, name);
+                }
+            }
+            let (bits, directive) = if word.length() == 2 {
+                (format!("{:04x}", word.instruction_bits), ".2byte")
+            } else {
+                (format!("{:08x}", word.instruction_bits), ".4byte")
+            };
+            let text = match word.instruction {
+                Some(instruction) => disassemble_with_config(
+                    instruction,
+                    word.instruction_bits,
+                    word.pc,
+                    disasm_config,
+                ),
+                None => format!("{}\t0x{:x}", directive, word.instruction_bits),
+            };
+            println!("{:>8x}:\t{:<18}\t{}", word.pc, bits, text);
+            if word.instruction.is_none() {
+                eprintln!(
+                    "ckb-decode: {:x}: {} does not decode under version {}",
+                    word.pc, bits, version
+                );
+                failures += 1;
+            }
+        }
+    }
+    failures
+}
@@ -1,8 +1,18 @@
 use super::*;
-use ckb_vm::instructions::{blank_instruction, i::nop, insts};
+use ckb_vm::instructions::{blank_instruction, extract_opcode, i::nop, insts};
 use ckb_vm::instructions::{Instruction, Itype, Rtype, Stype, Utype};
 use ckb_vm_definitions::registers::SP;
 
+// Immediate extraction is generated from the layouts in templates/rvc
+use super::rvc_decoder::{
+    addi16sp_immediate, addi4spn_uimmediate, b_immediate, encode_addi16sp_immediate,
+    encode_addi4spn_uimmediate, encode_b_immediate, encode_fld_uimmediate, encode_fldsp_uimmediate,
+    encode_fsdsp_uimmediate, encode_immediate, encode_j_immediate, encode_lwsp_uimmediate,
+    encode_sw_uimmediate, encode_swsp_uimmediate, encode_uimmediate, fld_uimmediate,
+    fldsp_uimmediate, fsdsp_uimmediate, immediate, j_immediate, lwsp_uimmediate, sw_uimmediate,
+    swsp_uimmediate, uimmediate,
+};
+
 // Notice the location of rs2 in RVC encoding is different from full encoding
 #[inline(always)]
 fn c_rs2(instruction_bits: u32) -> usize {
@@ -18,78 +28,25 @@ fn compact_register_number(instruction_bits: u32, least_bit: usize) -> usize {
     x(instruction_bits, least_bit, 3, 0) as usize + 8
 }
 
-// [12]  => imm[5]
-// [6:2] => imm[4:0]
-fn immediate(instruction_bits: u32) -> i32 {
-    (x(instruction_bits, 2, 5, 0) | xs(instruction_bits, 12, 1, 5)) as i32
-}
-
-// [12]  => imm[5]
-// [6:2] => imm[4:0]
-fn uimmediate(instruction_bits: u32) -> u32 {
-    x(instruction_bits, 2, 5, 0) | x(instruction_bits, 12, 1, 5)
-}
-
-// [12:2] => imm[11|4|9:8|10|6|7|3:1|5]
-fn j_immediate(instruction_bits: u32) -> i32 {
-    (x(instruction_bits, 3, 3, 1)
-        | x(instruction_bits, 11, 1, 4)
-        | x(instruction_bits, 2, 1, 5)
-        | x(instruction_bits, 7, 1, 6)
-        | x(instruction_bits, 6, 1, 7)
-        | x(instruction_bits, 9, 2, 8)
-        | x(instruction_bits, 8, 1, 10)
-        | xs(instruction_bits, 12, 1, 11)) as i32
-}
-
-// [12:10] => uimm[5:3]
-// [6:5]   => uimm[7:6]
-fn fld_uimmediate(instruction_bits: u32) -> u32 {
-    x(instruction_bits, 10, 3, 3) | x(instruction_bits, 5, 2, 6)
-}
-
-// [10:12] => uimm[5:3]
-// [5:6]   => uimm[2|6]
-fn sw_uimmediate(instruction_bits: u32) -> u32 {
-    x(instruction_bits, 6, 1, 2) | x(instruction_bits, 10, 3, 3) | x(instruction_bits, 5, 1, 6)
-}
-
-// [12]  => uimm[5]
-// [6:2] => uimm[4:2|7:6]
-fn lwsp_uimmediate(instruction_bits: u32) -> u32 {
-    x(instruction_bits, 4, 3, 2) | x(instruction_bits, 12, 1, 5) | x(instruction_bits, 2, 2, 6)
-}
-
-// [12]  => uimm[5]
-// [6:2] => uimm[4:3|8:6]
-fn fldsp_uimmediate(instruction_bits: u32) -> u32 {
-    x(instruction_bits, 5, 2, 3) | x(instruction_bits, 12, 1, 5) | x(instruction_bits, 2, 3, 6)
-}
-
-// [12:7] => uimm[5:3|8:6]
-fn fsdsp_uimmediate(instruction_bits: u32) -> u32 {
-    x(instruction_bits, 10, 3, 3) | x(instruction_bits, 7, 3, 6)
-}
-
-// [12:7] => uimm[5:2|7:6]
-fn swsp_uimmediate(instruction_bits: u32) -> u32 {
-    x(instruction_bits, 9, 4, 2) | x(instruction_bits, 7, 2, 6)
+#[inline(always)]
+fn encode_c_rs2(rs2: usize) -> u32 {
+    x(rs2 as u32, 0, 5, 2)
 }
 
-// [12:10] => imm[8|4:3]
-// [6:2]   => imm[7:6|2:1|5]
-fn b_immediate(instruction_bits: u32) -> i32 {
-    (x(instruction_bits, 3, 2, 1)
-        | x(instruction_bits, 10, 2, 3)
-        | x(instruction_bits, 2, 1, 5)
-        | x(instruction_bits, 5, 2, 6)
-        | xs(instruction_bits, 12, 1, 8)) as i32
+// Inverse of compact_register_number, None outside of x8 - x15
+#[inline(always)]
+fn encode_compact_register_number(register: usize, least_bit: usize) -> Option<u32> {
+    if (8..16).contains(&register) {
+        Some(x(register as u32 - 8, 0, 3, least_bit))
+    } else {
+        None
+    }
 }
 
 pub fn cadd_ebreak_jalr(
     instruction_bits: u32,
     _: insts::InstructionOpcode,
-    config: &FactoryConfig,
+    _: &FactoryConfig,
 ) -> Option<Instruction> {
     let rd = rd(instruction_bits);
     let rs2 = c_rs2(instruction_bits);
@@ -102,11 +59,9 @@ pub fn cadd_ebreak_jalr(
         (rd, rs2) => {
             if rd != 0 {
                 Some(Rtype::new(insts::OP_ADD, rd, rd, rs2).0)
-            } else if config.version >= 1 {
-                // HINTs
-                Some(nop())
             } else {
-                None
+                // HINTs, see min_version in templates/rvc
+                Some(nop())
             }
         }
     }
@@ -122,7 +77,6 @@ pub fn caddi_cnop(
     let nzimm = immediate(instruction_bits);
     let rd = rd(instruction_bits);
     match (rd, nzimm) {
-        (0, 0) => None,
         (0, _) => {
             // C.NOP
             Some(nop())
@@ -141,13 +95,9 @@ pub fn caddi16sp(
     _: &FactoryConfig,
 ) -> Option<Instruction> {
     // C.ADDI16SP is only valid when nzimm!=0, code point with nzimm=0 is reserved
-    let nzimm = x(instruction_bits, 6, 1, 4)
-        | x(instruction_bits, 2, 1, 5)
-        | x(instruction_bits, 5, 1, 6)
-        | x(instruction_bits, 3, 2, 7)
-        | xs(instruction_bits, 12, 1, 9);
+    let nzimm = addi16sp_immediate(instruction_bits);
     if nzimm != 0 {
-        Some(Itype::new_s(insts::OP_ADDI, SP, SP, nzimm as i32).0)
+        Some(Itype::new_s(insts::OP_ADDI, SP, SP, nzimm).0)
     } else {
         // reserved
         None
@@ -160,10 +110,7 @@ pub fn caddi4spn(
     _: &FactoryConfig,
 ) -> Option<Instruction> {
     // C.ADDI4SPN is only valid when nzuimm=0, the code points with nzuimm=0 are reserved
-    let nzuimm = x(instruction_bits, 6, 1, 2)
-        | x(instruction_bits, 5, 1, 3)
-        | x(instruction_bits, 11, 2, 4)
-        | x(instruction_bits, 7, 4, 6);
+    let nzuimm = addi4spn_uimmediate(instruction_bits);
     if nzuimm != 0 {
         // C.ADDI4SPN
         Some(
@@ -205,22 +152,19 @@ pub fn caddiw_jal(
 pub fn caddw(
     instruction_bits: u32,
     _: insts::InstructionOpcode,
-    config: &FactoryConfig,
+    _: &FactoryConfig,
 ) -> Option<Instruction> {
-    // C.ADDW is 64/128 only.
+    // C.ADDW is 64/128 only, see xlen in templates/rvc
     let rd = compact_register_number(instruction_bits, 7);
-    if config.rv64 {
-        return Some(
-            Rtype::new(
-                insts::OP_ADDW,
-                rd,
-                rd,
-                compact_register_number(instruction_bits, 2),
-            )
-            .0,
-        );
-    }
-    None
+    Some(
+        Rtype::new(
+            insts::OP_ADDW,
+            rd,
+            rd,
+            compact_register_number(instruction_bits, 2),
+        )
+        .0,
+    )
 }
 
 pub fn cand(
@@ -292,7 +236,7 @@ pub fn cj(
 pub fn cjr_cmv(
     instruction_bits: u32,
     _: insts::InstructionOpcode,
-    config: &FactoryConfig,
+    _: &FactoryConfig,
 ) -> Option<Instruction> {
     // C.JR and C.MV share the same mask
     // C.JR is only valid when rs1!=0, whose code point is reserved
@@ -312,11 +256,9 @@ pub fn cjr_cmv(
         if rs1 != 0 {
             // C.MV
             Some(Rtype::new(insts::OP_ADD, rs1, 0, rs2).0)
-        } else if config.version >= 1 {
-            // HINTS
-            Some(nop())
         } else {
-            None
+            // HINTS, see min_version in templates/rvc
+            Some(nop())
         }
     }
 }
@@ -324,12 +266,9 @@ pub fn cjr_cmv(
 pub fn cld(
     instruction_bits: u32,
     _: insts::InstructionOpcode,
-    config: &FactoryConfig,
+    _: &FactoryConfig,
 ) -> Option<Instruction> {
-    // c.ld is 64/128 only
-    if config.rv32 {
-        return None;
-    }
+    // c.ld is 64/128 only, see xlen in templates/rvc
     // C.LD
     Some(
         Itype::new_u(
@@ -345,12 +284,9 @@ pub fn cld(
 pub fn cldsp(
     instruction_bits: u32,
     _: insts::InstructionOpcode,
-    config: &FactoryConfig,
+    _: &FactoryConfig,
 ) -> Option<Instruction> {
-    // C.LDSP is 64/128 only, and only valid when rd!=0.
-    if config.rv32 {
-        return None;
-    }
+    // C.LDSP is 64/128 only (see xlen in templates/rvc), and only valid when rd!=0.
     let rd = rd(instruction_bits);
     if rd != 0 {
         Some(Itype::new_u(insts::OP_LD, rd, SP, fldsp_uimmediate(instruction_bits)).0)
@@ -363,7 +299,7 @@ pub fn cldsp(
 pub fn cli(
     instruction_bits: u32,
     _: insts::InstructionOpcode,
-    config: &FactoryConfig,
+    _: &FactoryConfig,
 ) -> Option<Instruction> {
     // C.LI is only valid when rd!=0, whose code points otherwise are HINTs
 
@@ -371,23 +307,24 @@ pub fn cli(
     if rd != 0 {
         // C.LI
         Some(Itype::new_s(insts::OP_ADDI, rd, 0, immediate(instruction_bits)).0)
-    } else if config.version >= 1 {
-        // HINTs
-        Some(nop())
     } else {
-        None
+        // HINTs, see min_version in templates/rvc
+        Some(nop())
     }
 }
 
-pub fn clui(
+pub fn clui_addi16sp(
     instruction_bits: u32,
-    _: insts::InstructionOpcode,
+    opcode: insts::InstructionOpcode,
     config: &FactoryConfig,
 ) -> Option<Instruction> {
     // C.LUI is only valid when rd!={0,2} and immediate != 0
     // code points with nzimm = 0 are reserved
     // the remaining code points with rd=0 are HINTs
     // the remaining code points with rd=2 are C.ADDI16SP
+    if rd(instruction_bits) == SP {
+        return caddi16sp(instruction_bits, opcode, config);
+    }
     let imm = immediate(instruction_bits) << 12;
     if imm == 0 {
         // reserved
@@ -396,11 +333,9 @@ pub fn clui(
     let rd = rd(instruction_bits);
     if rd != 0 {
         Some(Utype::new_s(insts::OP_LUI, rd, imm).0)
-    } else if config.version > 1 {
-        // HINTS
-        Some(nop())
     } else {
-        panic!("This cannot happen as CLUI and CADDI16SP should have different mask")
+        // HINTS, see min_version in templates/rvc
+        Some(nop())
     }
 }
 
@@ -457,43 +392,35 @@ pub fn cor(
 pub fn csd(
     instruction_bits: u32,
     _: insts::InstructionOpcode,
-    config: &FactoryConfig,
+    _: &FactoryConfig,
 ) -> Option<Instruction> {
-    // C.SD is 64/128 only
-    if config.rv32 {
-        None
-    } else {
-        Some(
-            Stype::new_u(
-                insts::OP_SD,
-                fld_uimmediate(instruction_bits),
-                compact_register_number(instruction_bits, 7),
-                compact_register_number(instruction_bits, 2),
-            )
-            .0,
+    // C.SD is 64/128 only, see xlen in templates/rvc
+    Some(
+        Stype::new_u(
+            insts::OP_SD,
+            fld_uimmediate(instruction_bits),
+            compact_register_number(instruction_bits, 7),
+            compact_register_number(instruction_bits, 2),
         )
-    }
+        .0,
+    )
 }
 
 pub fn csdsp(
     instruction_bits: u32,
     _: insts::InstructionOpcode,
-    config: &FactoryConfig,
+    _: &FactoryConfig,
 ) -> Option<Instruction> {
-    // C.SDSP is 64/128 only
-    if config.rv32 {
-        None
-    } else {
-        Some(
-            Stype::new_u(
-                insts::OP_SD,
-                fsdsp_uimmediate(instruction_bits),
-                2,
-                c_rs2(instruction_bits),
-            )
-            .0,
+    // C.SDSP is 64/128 only, see xlen in templates/rvc
+    Some(
+        Stype::new_u(
+            insts::OP_SD,
+            fsdsp_uimmediate(instruction_bits),
+            2,
+            c_rs2(instruction_bits),
         )
-    }
+        .0,
+    )
 }
 
 pub fn cslli(
@@ -509,11 +436,9 @@ pub fn cslli(
     if rd != 0 && uimm != 0 {
         // C.SLLI
         Some(Itype::new_u(insts::OP_SLLI, rd, rd, uimm & u32::from(config.shift_masks)).0)
-    } else if config.version >= 1 {
-        // HINTs
-        Some(nop())
     } else {
-        None
+        // HINTs, see min_version in templates/rvc
+        Some(nop())
     }
 }
 
@@ -557,13 +482,10 @@ pub fn csub(
 pub fn csubw(
     instruction_bits: u32,
     _: insts::InstructionOpcode,
-    config: &FactoryConfig,
+    _: &FactoryConfig,
 ) -> Option<Instruction> {
-    // C.SUBW is 64/128 only
+    // C.SUBW is 64/128 only, see xlen in templates/rvc
     let rd = compact_register_number(instruction_bits, 7);
-    if config.rv32 {
-        return None;
-    }
     Some(
         Rtype::new(
             insts::OP_SUBW,
@@ -624,3 +546,722 @@ pub fn cxor(
         .0,
     )
 }
+
+// Encoders, the inverses of the handlers above. Handlers shared by several
+// entries tell them apart by the opcode, like the decoders by the bits.
+pub fn cadd_ebreak_jalr_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    match extract_opcode(instruction) {
+        insts::OP_EBREAK => Some(match_bits),
+        insts::OP_JALR => {
+            let i = Itype(instruction);
+            if i.rd() != 1 || i.immediate_s() != 0 {
+                return None;
+            }
+            Some(match_bits | encode_rd(i.rs1()))
+        }
+        insts::OP_ADD => {
+            let i = Rtype(instruction);
+            if i.rd() != i.rs1() {
+                return None;
+            }
+            Some(match_bits | encode_rd(i.rd()) | encode_c_rs2(i.rs2()))
+        }
+        _ => None,
+    }
+}
+
+pub fn caddi_cnop_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = Itype(instruction);
+    if i.rd() != i.rs1() {
+        return None;
+    }
+    Some(match_bits | encode_rd(i.rd()) | encode_immediate(i.immediate_s()))
+}
+
+pub fn caddi4spn_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = Itype(instruction);
+    if i.rs1() != SP {
+        return None;
+    }
+    Some(
+        match_bits
+            | encode_compact_register_number(i.rd(), 2)?
+            | encode_addi4spn_uimmediate(i.immediate_u()),
+    )
+}
+
+pub fn caddiw_jal_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    if extract_opcode(instruction) == insts::OP_JAL {
+        let i = Utype(instruction);
+        if i.rd() != 1 {
+            return None;
+        }
+        Some(match_bits | encode_j_immediate(i.immediate_s()))
+    } else {
+        let i = Itype(instruction);
+        if i.rd() != i.rs1() {
+            return None;
+        }
+        Some(match_bits | encode_rd(i.rd()) | encode_immediate(i.immediate_s()))
+    }
+}
+
+// C.ADDW, C.AND, C.OR, C.SUB, C.SUBW and C.XOR
+fn ca_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = Rtype(instruction);
+    if i.rd() != i.rs1() {
+        return None;
+    }
+    Some(
+        match_bits
+            | encode_compact_register_number(i.rd(), 7)?
+            | encode_compact_register_number(i.rs2(), 2)?,
+    )
+}
+
+pub fn caddw_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    ca_encoder(instruction, match_bits)
+}
+
+pub fn cand_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    ca_encoder(instruction, match_bits)
+}
+
+pub fn candi_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = Itype(instruction);
+    if i.rd() != i.rs1() {
+        return None;
+    }
+    Some(
+        match_bits | encode_compact_register_number(i.rd(), 7)? | encode_immediate(i.immediate_s()),
+    )
+}
+
+// C.BEQZ and C.BNEZ
+fn cb_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = Stype(instruction);
+    if i.rs2() != 0 {
+        return None;
+    }
+    Some(
+        match_bits
+            | encode_compact_register_number(i.rs1(), 7)?
+            | encode_b_immediate(i.immediate_s()),
+    )
+}
+
+pub fn cbeqz_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    cb_encoder(instruction, match_bits)
+}
+
+pub fn cbnez_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    cb_encoder(instruction, match_bits)
+}
+
+pub fn cj_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = Utype(instruction);
+    if i.rd() != 0 {
+        return None;
+    }
+    Some(match_bits | encode_j_immediate(i.immediate_s()))
+}
+
+pub fn cjr_cmv_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    if extract_opcode(instruction) == insts::OP_JALR {
+        let i = Itype(instruction);
+        if i.rd() != 0 || i.immediate_s() != 0 {
+            return None;
+        }
+        Some(match_bits | encode_rd(i.rs1()))
+    } else {
+        let i = Rtype(instruction);
+        if i.rs1() != 0 {
+            return None;
+        }
+        Some(match_bits | encode_rd(i.rd()) | encode_c_rs2(i.rs2()))
+    }
+}
+
+pub fn cld_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = Itype(instruction);
+    Some(
+        match_bits
+            | encode_compact_register_number(i.rd(), 2)?
+            | encode_compact_register_number(i.rs1(), 7)?
+            | encode_fld_uimmediate(i.immediate_u()),
+    )
+}
+
+pub fn cldsp_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = Itype(instruction);
+    if i.rs1() != SP {
+        return None;
+    }
+    Some(match_bits | encode_rd(i.rd()) | encode_fldsp_uimmediate(i.immediate_u()))
+}
+
+pub fn cli_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = Itype(instruction);
+    if i.rs1() != 0 {
+        return None;
+    }
+    Some(match_bits | encode_rd(i.rd()) | encode_immediate(i.immediate_s()))
+}
+
+pub fn clui_addi16sp_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    if extract_opcode(instruction) == insts::OP_LUI {
+        let i = Utype(instruction);
+        Some(match_bits | encode_rd(i.rd()) | encode_immediate(i.immediate_s() >> 12))
+    } else {
+        let i = Itype(instruction);
+        if i.rd() != SP || i.rs1() != SP {
+            return None;
+        }
+        Some(match_bits | encode_rd(SP) | encode_addi16sp_immediate(i.immediate_s()))
+    }
+}
+
+pub fn clw_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = Itype(instruction);
+    Some(
+        match_bits
+            | encode_compact_register_number(i.rd(), 2)?
+            | encode_compact_register_number(i.rs1(), 7)?
+            | encode_sw_uimmediate(i.immediate_u()),
+    )
+}
+
+pub fn clwsp_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = Itype(instruction);
+    if i.rs1() != SP {
+        return None;
+    }
+    Some(match_bits | encode_rd(i.rd()) | encode_lwsp_uimmediate(i.immediate_u()))
+}
+
+pub fn cor_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    ca_encoder(instruction, match_bits)
+}
+
+pub fn csd_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = Stype(instruction);
+    Some(
+        match_bits
+            | encode_compact_register_number(i.rs1(), 7)?
+            | encode_compact_register_number(i.rs2(), 2)?
+            | encode_fld_uimmediate(i.immediate_u()),
+    )
+}
+
+pub fn csdsp_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = Stype(instruction);
+    if i.rs1() != SP {
+        return None;
+    }
+    Some(match_bits | encode_c_rs2(i.rs2()) | encode_fsdsp_uimmediate(i.immediate_u()))
+}
+
+pub fn cslli_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = Itype(instruction);
+    if i.rd() != i.rs1() {
+        return None;
+    }
+    // shamt=0 is a HINT, a shift by 0 comes from an RV32 word with shamt[5]
+    // set that cslli masks
+    let shamt = match i.immediate_u() {
+        0 => 32,
+        shamt => shamt,
+    };
+    Some(match_bits | encode_rd(i.rd()) | encode_uimmediate(shamt))
+}
+
+// C.SRAI and C.SRLI
+fn cshift_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = Itype(instruction);
+    if i.rd() != i.rs1() {
+        return None;
+    }
+    Some(
+        match_bits
+            | encode_compact_register_number(i.rd(), 7)?
+            | encode_uimmediate(i.immediate_u()),
+    )
+}
+
+pub fn csrai_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    cshift_encoder(instruction, match_bits)
+}
+
+pub fn csrli_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    cshift_encoder(instruction, match_bits)
+}
+
+pub fn csub_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    ca_encoder(instruction, match_bits)
+}
+
+pub fn csubw_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    ca_encoder(instruction, match_bits)
+}
+
+pub fn csw_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = Stype(instruction);
+    Some(
+        match_bits
+            | encode_compact_register_number(i.rs1(), 7)?
+            | encode_compact_register_number(i.rs2(), 2)?
+            | encode_sw_uimmediate(i.immediate_u()),
+    )
+}
+
+pub fn cswsp_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = Stype(instruction);
+    if i.rs1() != SP {
+        return None;
+    }
+    Some(match_bits | encode_c_rs2(i.rs2()) | encode_swsp_uimmediate(i.immediate_u()))
+}
+
+pub fn cxor_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    ca_encoder(instruction, match_bits)
+}
+
+// Expansion of compressed words into the 32-bit base encodings they stand for,
+// as tabulated in the RVC chapter of the spec. It works on the bits alone,
+// not on the handlers above, so rvi_decoder can check the latter.
+fn expand_i(match_bits: u32, rd: usize, rs1: usize, imm: i32) -> u32 {
+    match_bits | encode_rd(rd) | encode_rs1(rs1) | encode_itype_immediate(imm)
+}
+
+fn expand_s(match_bits: u32, rs1: usize, rs2: usize, imm: i32) -> u32 {
+    match_bits | encode_rs1(rs1) | encode_rs2(rs2) | encode_stype_immediate(imm)
+}
+
+fn expand_r(match_bits: u32, rd: usize, rs1: usize, rs2: usize) -> u32 {
+    match_bits | encode_rd(rd) | encode_rs1(rs1) | encode_rs2(rs2)
+}
+
+// RV32 shift amounts with shamt[5] set are reserved for custom extensions
+fn expand_shamt(shamt: u32, config: &FactoryConfig) -> Option<i32> {
+    if config.rv32 && shamt >= 32 {
+        None
+    } else {
+        Some(shamt as i32)
+    }
+}
+
+// Returns the 32-bit word of a valid compressed word, HINTs expand to the
+// base instructions writing x0 they are defined as. Reserved code points and
+// words of instructions not in templates/rvc (C.FLD and friends) give None.
+pub fn expand(instruction_bits: u32, config: &FactoryConfig) -> Option<u32> {
+    let rd = rd(instruction_bits);
+    let rs2 = c_rs2(instruction_bits);
+    // rd'/rs2' at [4:2] and rd'/rs1' at [9:7]
+    let rd_c = compact_register_number(instruction_bits, 2);
+    let rs1_c = compact_register_number(instruction_bits, 7);
+    let funct3 = x(instruction_bits, 13, 3, 0);
+    match (instruction_bits & 0x3, funct3) {
+        // C.ADDI4SPN
+        (0b00, 0b000) => match addi4spn_uimmediate(instruction_bits) {
+            0 => None,
+            nzuimm => Some(expand_i(0x13, rd_c, SP, nzuimm as i32)),
+        },
+        // C.LW
+        (0b00, 0b010) => Some(expand_i(
+            0x2003,
+            rd_c,
+            rs1_c,
+            sw_uimmediate(instruction_bits) as i32,
+        )),
+        // C.LD
+        (0b00, 0b011) if config.rv64 => Some(expand_i(
+            0x3003,
+            rd_c,
+            rs1_c,
+            fld_uimmediate(instruction_bits) as i32,
+        )),
+        // C.SW
+        (0b00, 0b110) => Some(expand_s(
+            0x2023,
+            rs1_c,
+            rd_c,
+            sw_uimmediate(instruction_bits) as i32,
+        )),
+        // C.SD
+        (0b00, 0b111) if config.rv64 => Some(expand_s(
+            0x3023,
+            rs1_c,
+            rd_c,
+            fld_uimmediate(instruction_bits) as i32,
+        )),
+        // C.ADDI, C.NOP
+        (0b01, 0b000) => Some(expand_i(0x13, rd, rd, immediate(instruction_bits))),
+        // C.JAL
+        (0b01, 0b001) if config.rv32 => {
+            Some(0x6f | encode_rd(1) | encode_jtype_immediate(j_immediate(instruction_bits)))
+        }
+        // C.ADDIW
+        (0b01, 0b001) => match rd {
+            0 => None,
+            rd => Some(expand_i(0x1b, rd, rd, immediate(instruction_bits))),
+        },
+        // C.LI
+        (0b01, 0b010) => Some(expand_i(0x13, rd, 0, immediate(instruction_bits))),
+        // C.ADDI16SP
+        (0b01, 0b011) if rd == SP => match addi16sp_immediate(instruction_bits) {
+            0 => None,
+            nzimm => Some(expand_i(0x13, SP, SP, nzimm)),
+        },
+        // C.LUI
+        (0b01, 0b011) => match immediate(instruction_bits) {
+            0 => None,
+            nzimm => Some(0x37 | encode_rd(rd) | encode_utype_immediate(nzimm << 12)),
+        },
+        (0b01, 0b100) => {
+            let shamt = uimmediate(instruction_bits);
+            match (x(instruction_bits, 10, 2, 0), x(instruction_bits, 12, 1, 0)) {
+                // C.SRLI
+                (0b00, _) => Some(expand_i(0x5013, rs1_c, rs1_c, expand_shamt(shamt, config)?)),
+                // C.SRAI
+                (0b01, _) => Some(expand_i(
+                    0x4000_5013,
+                    rs1_c,
+                    rs1_c,
+                    expand_shamt(shamt, config)?,
+                )),
+                // C.ANDI
+                (0b10, _) => Some(expand_i(0x7013, rs1_c, rs1_c, immediate(instruction_bits))),
+                (0b11, 0) => {
+                    let match_bits = match x(instruction_bits, 5, 2, 0) {
+                        // C.SUB
+                        0b00 => 0x4000_0033,
+                        // C.XOR
+                        0b01 => 0x4033,
+                        // C.OR
+                        0b10 => 0x6033,
+                        // C.AND
+                        _ => 0x7033,
+                    };
+                    Some(expand_r(match_bits, rs1_c, rs1_c, rd_c))
+                }
+                (_, _) if config.rv64 => match x(instruction_bits, 5, 2, 0) {
+                    // C.SUBW
+                    0b00 => Some(expand_r(0x4000_003b, rs1_c, rs1_c, rd_c)),
+                    // C.ADDW
+                    0b01 => Some(expand_r(0x3b, rs1_c, rs1_c, rd_c)),
+                    _ => None,
+                },
+                (_, _) => None,
+            }
+        }
+        // C.J
+        (0b01, 0b101) => Some(0x6f | encode_jtype_immediate(j_immediate(instruction_bits))),
+        // C.BEQZ, C.BNEZ
+        (0b01, 0b110) | (0b01, 0b111) => Some(
+            (funct3 & 1) << 12
+                | 0x63
+                | encode_rs1(rs1_c)
+                | encode_btype_immediate(b_immediate(instruction_bits)),
+        ),
+        // C.SLLI
+        (0b10, 0b000) => Some(expand_i(
+            0x1013,
+            rd,
+            rd,
+            expand_shamt(uimmediate(instruction_bits), config)?,
+        )),
+        // C.LWSP
+        (0b10, 0b010) => match rd {
+            0 => None,
+            rd => Some(expand_i(
+                0x2003,
+                rd,
+                SP,
+                lwsp_uimmediate(instruction_bits) as i32,
+            )),
+        },
+        // C.LDSP
+        (0b10, 0b011) if config.rv64 => match rd {
+            0 => None,
+            rd => Some(expand_i(
+                0x3003,
+                rd,
+                SP,
+                fldsp_uimmediate(instruction_bits) as i32,
+            )),
+        },
+        (0b10, 0b100) => match (x(instruction_bits, 12, 1, 0), rd, rs2) {
+            // C.JR with rs1=0 is reserved
+            (0, 0, 0) => None,
+            // C.JR
+            (0, rs1, 0) => Some(expand_i(0x67, 0, rs1, 0)),
+            // C.MV
+            (0, rd, rs2) => Some(expand_r(0x33, rd, 0, rs2)),
+            // C.EBREAK
+            (_, 0, 0) => Some(0x0010_0073),
+            // C.JALR
+            (_, rs1, 0) => Some(expand_i(0x67, 1, rs1, 0)),
+            // C.ADD
+            (_, rd, rs2) => Some(expand_r(0x33, rd, rd, rs2)),
+        },
+        // C.SWSP
+        (0b10, 0b110) => Some(expand_s(
+            0x2023,
+            SP,
+            rs2,
+            swsp_uimmediate(instruction_bits) as i32,
+        )),
+        // C.SDSP
+        (0b10, 0b111) if config.rv64 => Some(expand_s(
+            0x3023,
+            SP,
+            rs2,
+            fsdsp_uimmediate(instruction_bits) as i32,
+        )),
+        _ => None,
+    }
+}
+
+// The reverse of expand: the compressed word of a 32-bit instruction, if the
+// registers fall in x8 - x15 where the format asks for it and the immediate
+// fits. Candidates come from the encoders and are kept only when they expand
+// back to instruction.
+#[cfg(feature = "rvi")]
+pub fn compress(instruction: Instruction, config: &FactoryConfig) -> Option<u16> {
+    let opcode = extract_opcode(instruction);
+    rvc_decoder::INSTRUCTION_META
+        .iter()
+        .filter(|meta| meta.opcode == opcode && meta.xlen.supports(config))
+        .filter_map(|meta| (meta.encoder)(instruction, meta.match_bits))
+        .find(|instruction_bits| {
+            expand(*instruction_bits, config)
+                .and_then(|expanded_bits| rvi_decoder::factory_with_config(expanded_bits, config))
+                == Some(instruction)
+        })
+        .map(|instruction_bits| instruction_bits as u16)
+}
+
+// Offsets of the 32-bit instructions in code that have a compressed form,
+// along with that form. Compressed words in code are skipped.
+#[cfg(feature = "rvi")]
+pub fn compressible(code: &[u8], config: &FactoryConfig) -> Vec<(usize, u16)> {
+    let mut result = Vec::new();
+    let mut offset = 0;
+    while offset + 2 <= code.len() {
+        if code[offset] & 0x3 != 0x3 {
+            offset += 2;
+            continue;
+        }
+        if offset + 4 > code.len() {
+            break;
+        }
+        let mut word = [0u8; 4];
+        word.copy_from_slice(&code[offset..offset + 4]);
+        if let Some(compressed) = rvi_decoder::factory_with_config(u32::from_le_bytes(word), config)
+            .and_then(|instruction| compress(instruction, config))
+        {
+            result.push((offset, compressed));
+        }
+        offset += 4;
+    }
+    result
+}
+
+#[cfg(all(test, feature = "rvi"))]
+mod tests {
+    use super::*;
+    use ckb_vm::instructions::{set_instruction_length_2, set_instruction_length_4};
+
+    // Every compressed word either decodes to what its expansion decodes to
+    // in rvi_decoder, or is rejected by both.
+    fn check_expand(config: &FactoryConfig) {
+        let hint = set_instruction_length_2(nop());
+        for instruction_bits in (0..=0xffffu32).filter(|bits| bits & 0x3 != 0x3) {
+            let compressed = rvc_decoder::factory_with_config(instruction_bits, config);
+            let expanded = expand(instruction_bits, config).map(|expanded_bits| {
+                rvi_decoder::factory_with_config(expanded_bits, config).unwrap_or_else(|| {
+                    panic!("{:x} expands to {:x}", instruction_bits, expanded_bits)
+                })
+            });
+            match (compressed, expanded) {
+                (None, None) => {}
+                // HINTs decode to nop, their expansions keep the operands
+                (Some(c), Some(_)) if c == hint => {}
+                (Some(c), Some(e)) => assert_eq!(
+                    set_instruction_length_4(c),
+                    set_instruction_length_2(e),
+                    "{:x}",
+                    instruction_bits
+                ),
+                // cslli, csrli and csrai mask shamt[5] on RV32 where the spec
+                // reserves it, see the TODO in cslli
+                (Some(_), None) if config.rv32 && x(instruction_bits, 12, 1, 0) == 1 => {
+                    assert!(matches!(
+                        (instruction_bits & 0x3, x(instruction_bits, 13, 3, 0)),
+                        (0b10, 0b000) | (0b01, 0b100)
+                    ))
+                }
+                (c, e) => panic!(
+                    "{:x} decodes to {:?}, expands to {:?}",
+                    instruction_bits, c, e
+                ),
+            }
+        }
+    }
+
+    // Pairs from llvm-mc -triple=riscv64 -mattr=+c -show-encoding, the
+    // second 0 when llvm-mc keeps the 32-bit form
+    const COMPRESSIBLE: [(u32, u16); 9] = [
+        (0x0f84b403, 0x7ce0), // ld s0, 248(s1)
+        (0x1004b403, 0),      // ld s0, 256(s1)
+        (0x40940433, 0x8c05), // sub s0, s0, s1
+        (0x406282b3, 0),      // sub t0, t0, t1
+        (0x3fc10413, 0x1fe0), // addi s0, sp, 1020
+        (0x40010413, 0),      // addi s0, sp, 1024
+        (0x010282b3, 0x92c2), // add t0, t0, a6
+        (0x0001f537, 0x657d), // lui a0, 31
+        (0x00020537, 0),      // lui a0, 32
+    ];
+
+    #[test]
+    fn test_compress() {
+        let config = FactoryConfig::new::<u64>(1);
+        for (instruction_bits, compressed) in COMPRESSIBLE {
+            let instruction = rvi_decoder::factory_with_config(instruction_bits, &config).unwrap();
+            assert_eq!(
+                compress(instruction, &config).unwrap_or(0),
+                compressed,
+                "{:x}",
+                instruction_bits
+            );
+        }
+        let code: Vec<u8> = COMPRESSIBLE
+            .iter()
+            .flat_map(|(instruction_bits, _)| instruction_bits.to_le_bytes())
+            .chain(0x7ce0u16.to_le_bytes())
+            .collect();
+        assert_eq!(
+            compressible(&code, &config),
+            [
+                (0, 0x7ce0),
+                (8, 0x8c05),
+                (16, 0x1fe0),
+                (24, 0x92c2),
+                (28, 0x657d)
+            ]
+        );
+    }
+
+    // Whatever expands to a non-HINT instruction compresses again
+    #[test]
+    fn test_compress_expanded() {
+        for config in [FactoryConfig::new::<u32>(1), FactoryConfig::new::<u64>(1)] {
+            let hint = set_instruction_length_2(nop());
+            for instruction_bits in (0..=0xffffu32).filter(|bits| bits & 0x3 != 0x3) {
+                if rvc_decoder::factory_with_config(instruction_bits, &config)
+                    .filter(|c| *c != hint)
+                    .is_none()
+                {
+                    continue;
+                }
+                if let Some(instruction) =
+                    expand(instruction_bits, &config).and_then(|expanded_bits| {
+                        rvi_decoder::factory_with_config(expanded_bits, &config)
+                    })
+                {
+                    let compressed = compress(instruction, &config)
+                        .unwrap_or_else(|| panic!("{:x} does not compress", instruction_bits));
+                    assert_eq!(
+                        expand(u32::from(compressed), &config),
+                        expand(instruction_bits, &config)
+                    );
+                }
+            }
+        }
+    }
+
+    // (instruction, HINT) pairs, the first of each from llvm-mc
+    // -triple=riscv64 -mattr=+c -show-encoding
+    const HINTS: [(u16, u16); 6] = [
+        (0x929a, 0x901a), // add t0, t1 and add zero, t1
+        (0x829a, 0x801a), // mv t0, t1 and mv zero, t1
+        (0x4295, 0x4015), // li t0, 5 and li zero, 5
+        (0x6285, 0x6005), // lui t0, 1 and lui zero, 1
+        (0x0286, 0x0006), // slli t0, 1 and slli zero, 1
+        (0x0286, 0x0282), // slli t0, 1 and slli t0, 0
+    ];
+
+    // HINTs need version 1, see min_version in templates/rvc
+    #[test]
+    fn test_hints() {
+        let decode = |instruction_bits: u16, version| {
+            rvc_decoder::factory_with_config(
+                u32::from(instruction_bits),
+                &FactoryConfig::new::<u64>(version),
+            )
+        };
+        let hint = set_instruction_length_2(nop());
+        for (instruction_bits, hint_bits) in HINTS {
+            assert!(decode(instruction_bits, 0).is_some());
+            assert_eq!(decode(instruction_bits, 0), decode(instruction_bits, 1));
+            assert_eq!(decode(hint_bits, 0), None, "{:x}", hint_bits);
+            assert_eq!(decode(hint_bits, 1), Some(hint), "{:x}", hint_bits);
+        }
+        // c.ebreak also matches the C.ADD HINT mask
+        assert_eq!(
+            decode(0x9002, 0),
+            Some(set_instruction_length_2(blank_instruction(
+                insts::OP_EBREAK
+            )))
+        );
+        assert_eq!(decode(0x9002, 1), decode(0x9002, 0));
+    }
+
+    // C.LUI with rd=0 used to panic before version 1, it is a HINT there
+    #[test]
+    fn test_clui_addi16sp() {
+        for version in [0, 1] {
+            let config = FactoryConfig::new::<u64>(version);
+            let decode =
+                |instruction_bits| rvc_decoder::factory_with_config(instruction_bits, &config);
+            // lui t0, 1 and addi sp, sp, 16 from llvm-mc as above
+            let lui = Utype(decode(0x6285).unwrap());
+            assert_eq!(extract_opcode(lui.0), insts::OP_LUI);
+            assert_eq!((lui.rd(), lui.immediate_s()), (5, 0x1000));
+            let addi = Itype(decode(0x6141).unwrap());
+            assert_eq!(extract_opcode(addi.0), insts::OP_ADDI);
+            assert_eq!((addi.rd(), addi.rs1(), addi.immediate_s()), (SP, SP, 16));
+            // lui zero, 1
+            let hint = Some(set_instruction_length_2(nop())).filter(|_| version >= 1);
+            assert_eq!(decode(0x6005), hint);
+            // nzimm=0 is reserved for both
+            assert_eq!(decode(0x6281), None);
+            assert_eq!(decode(0x6101), None);
+        }
+    }
+
+    // ld s0, 248(s1), ld a0, 8(sp), sd s0, 248(s1), sd a0, 8(sp),
+    // addw s0, s1 and subw s0, s1 from llvm-mc as above, all reserved or
+    // floating point on RV32
+    const RV64_ONLY: [u16; 6] = [0x7ce0, 0x6522, 0xfce0, 0xe42a, 0x9c25, 0x9c05];
+
+    // The builders leave xlen to the factory, see xlen in templates/rvc
+    #[test]
+    fn test_rv64_only() {
+        for instruction_bits in RV64_ONLY.map(u32::from) {
+            let rv32 = FactoryConfig::new::<u32>(1);
+            let rv64 = FactoryConfig::new::<u64>(1);
+            assert_eq!(
+                rvc_decoder::factory_with_config(instruction_bits, &rv32),
+                None
+            );
+            assert!(rvc_decoder::factory_with_config(instruction_bits, &rv64).is_some());
+        }
+    }
+
+    #[test]
+    fn test_expand() {
+        check_expand(&FactoryConfig::new::<u32>(1));
+        check_expand(&FactoryConfig::new::<u64>(1));
+    }
+}
@@ -57,11 +57,63 @@ pub fn btype_immediate(instruction_bits: u32) -> i32 {
         | xs(instruction_bits, 31, 1, 12)) as i32
 }
 
+#[inline(always)]
+pub fn jtype_immediate(instruction_bits: u32) -> i32 {
+    (x(instruction_bits, 21, 10, 1)
+        | x(instruction_bits, 20, 1, 11)
+        | x(instruction_bits, 12, 8, 12)
+        | xs(instruction_bits, 31, 1, 20)) as i32
+}
+
 #[inline(always)]
 pub fn stype_immediate(instruction_bits: u32) -> i32 {
     (x(instruction_bits, 7, 5, 0) | xs(instruction_bits, 25, 7, 5)) as i32
 }
 
+// Inverses of the field extractions above, for the encoders
+#[inline(always)]
+pub fn encode_rd(rd: usize) -> u32 {
+    x(rd as u32, 0, 5, 7)
+}
+
+#[inline(always)]
+pub fn encode_rs1(rs1: usize) -> u32 {
+    x(rs1 as u32, 0, 5, 15)
+}
+
+#[inline(always)]
+pub fn encode_rs2(rs2: usize) -> u32 {
+    x(rs2 as u32, 0, 5, 20)
+}
+
+#[inline(always)]
+pub fn encode_utype_immediate(imm: i32) -> u32 {
+    imm as u32 & 0xffff_f000
+}
+
+#[inline(always)]
+pub fn encode_itype_immediate(imm: i32) -> u32 {
+    x(imm as u32, 0, 12, 20)
+}
+
+#[inline(always)]
+pub fn encode_btype_immediate(imm: i32) -> u32 {
+    let imm = imm as u32;
+    x(imm, 1, 4, 8) | x(imm, 5, 6, 25) | x(imm, 11, 1, 7) | x(imm, 12, 1, 31)
+}
+
+#[inline(always)]
+pub fn encode_jtype_immediate(imm: i32) -> u32 {
+    let imm = imm as u32;
+    x(imm, 1, 10, 21) | x(imm, 11, 1, 20) | x(imm, 12, 8, 12) | x(imm, 20, 1, 31)
+}
+
+#[inline(always)]
+pub fn encode_stype_immediate(imm: i32) -> u32 {
+    let imm = imm as u32;
+    x(imm, 0, 5, 7) | x(imm, 5, 7, 25)
+}
+
 pub fn us_builder(
     instruction_bits: u32,
     opcode: insts::InstructionOpcode,
@@ -77,6 +129,21 @@ pub fn us_builder(
     )
 }
 
+pub fn uj_builder(
+    instruction_bits: u32,
+    opcode: insts::InstructionOpcode,
+    _: &FactoryConfig,
+) -> Option<Instruction> {
+    Some(
+        Utype::new_s(
+            opcode,
+            rd(instruction_bits),
+            jtype_immediate(instruction_bits),
+        )
+        .0,
+    )
+}
+
 pub fn is_builder(
     instruction_bits: u32,
     opcode: insts::InstructionOpcode,
@@ -148,3 +215,52 @@ pub fn r_builder(
         .0,
     )
 }
+
+pub fn us_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = Utype(instruction);
+    Some(match_bits | encode_rd(i.rd()) | encode_utype_immediate(i.immediate_s()))
+}
+
+pub fn uj_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = Utype(instruction);
+    Some(match_bits | encode_rd(i.rd()) | encode_jtype_immediate(i.immediate_s()))
+}
+
+pub fn is_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = Itype(instruction);
+    Some(
+        match_bits
+            | encode_rd(i.rd())
+            | encode_rs1(i.rs1())
+            | encode_itype_immediate(i.immediate_s()),
+    )
+}
+
+pub fn sb_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = Stype(instruction);
+    Some(
+        match_bits
+            | encode_rs1(i.rs1())
+            | encode_rs2(i.rs2())
+            | encode_btype_immediate(i.immediate_s()),
+    )
+}
+
+pub fn ss_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = Stype(instruction);
+    Some(
+        match_bits
+            | encode_rs1(i.rs1())
+            | encode_rs2(i.rs2())
+            | encode_stype_immediate(i.immediate_s()),
+    )
+}
+
+pub fn blank_inst_encoder(_: Instruction, match_bits: u32) -> Option<u32> {
+    Some(match_bits)
+}
+
+pub fn r_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = Rtype(instruction);
+    Some(match_bits | encode_rd(i.rd()) | encode_rs1(i.rs1()) | encode_rs2(i.rs2()))
+}
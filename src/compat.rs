@@ -0,0 +1,245 @@
+// Checks a program against the ckb-vm a script is deployed on: the VM version
+// decides e.g. whether HINTs decode, the ISA profile which extensions exist.
+use super::elf::{decode_with_meta, executable_sections};
+use super::v::VectorProfile;
+use super::*;
+use std::fmt;
+
+// The newest version any handler tells apart
+pub const LATEST_VERSION: u32 = 1;
+
+// Extensions in the ext column of the templates, grouped the way profiles
+// name them
+const BASE: [&str; 4] = ["I", "M", "C", "Zifencei"];
+const B: [&str; 4] = ["Zba", "Zbb", "Zbc", "Zbs"];
+const ZVK: [&str; 6] = ["Zvbb", "Zvbc", "Zvkb", "Zvkg", "Zvkned", "Zvknh"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IsaProfile {
+    extensions: Vec<String>,
+    vector_profile: Option<VectorProfile>,
+}
+
+impl IsaProfile {
+    // RV64IMC, what every ckb-vm version runs
+    pub fn imc() -> Self {
+        Self {
+            extensions: BASE.iter().map(|name| name.to_string()).collect(),
+            vector_profile: None,
+        }
+    }
+
+    pub fn with_extension(mut self, name: &str) -> Self {
+        if !self.has_extension(name) {
+            self.extensions.push(name.to_string());
+        }
+        self
+    }
+
+    // Zba, Zbb, Zbc and Zbs
+    pub fn with_b(self) -> Self {
+        B.iter()
+            .fold(self, |profile, name| profile.with_extension(name))
+    }
+
+    // V, or one of the embedded Zve* profiles
+    pub fn with_vector_profile(self, profile: VectorProfile) -> Self {
+        let mut result = self.with_extension("V");
+        result.vector_profile = Some(profile);
+        result
+    }
+
+    pub fn has_extension(&self, name: &str) -> bool {
+        self.extensions
+            .iter()
+            .any(|extension| extension.eq_ignore_ascii_case(name))
+    }
+
+    // Comma separated, e.g. "imc,b,zve32x,zvbb": imc is implied, b stands for
+    // the Zb* extensions, v and zve* for the vector profiles, zvk for all
+    // vector crypto extensions. Other names are taken as they are.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut profile = Self::imc();
+        for name in text.split(',').map(str::trim) {
+            profile = match name.to_ascii_lowercase().as_str() {
+                "imc" => profile,
+                "b" => profile.with_b(),
+                "zvk" => ZVK
+                    .iter()
+                    .fold(profile, |profile, name| profile.with_extension(name)),
+                "v" => profile.with_vector_profile(VectorProfile::V),
+                "zve32x" => profile.with_vector_profile(VectorProfile::Zve32x),
+                "zve32f" => profile.with_vector_profile(VectorProfile::Zve32f),
+                "zve64x" => profile.with_vector_profile(VectorProfile::Zve64x),
+                "zve64f" => profile.with_vector_profile(VectorProfile::Zve64f),
+                "zve64d" => profile.with_vector_profile(VectorProfile::Zve64d),
+                "" => return None,
+                _ => profile.with_extension(name),
+            };
+        }
+        Some(profile)
+    }
+}
+
+// Why the deployed VM would not run a word
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reason {
+    // No decoder knows the word
+    Unknown,
+    // Decodes from this version on
+    Version(u32),
+    // Part of an extension missing from the profile
+    Extension(&'static str),
+    // Beyond the vector profile, e.g. SEW=64 under Zve32x
+    VectorProfile,
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Reason::Unknown => write!(f, "unknown instruction"),
+            Reason::Version(version) => write!(f, "requires version {}", version),
+            Reason::Extension(extension) => write!(f, "requires {}", extension),
+            Reason::VectorProfile => write!(f, "not in the vector profile"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rejection {
+    pub pc: u64,
+    pub instruction_bits: u32,
+    pub reason: Reason,
+}
+
+// None if the word runs under version and profile. Vector crypto is always
+// decoded, so that it is reported as a missing extension.
+pub fn check_word(instruction_bits: u32, version: u32, profile: &IsaProfile) -> Option<Reason> {
+    let config = FactoryConfig::new::<u64>(version).with_vector_crypto();
+    let target = match profile.vector_profile {
+        Some(vector_profile) => config.with_vector_profile(vector_profile),
+        None => config,
+    };
+    if let Some((_, meta)) = decode_with_meta(instruction_bits, &target) {
+        if profile.has_extension(meta.extension) {
+            return None;
+        }
+        return Some(Reason::Extension(meta.extension));
+    }
+    // float operations only decode under a profile with F
+    let float = config.with_vector_profile(VectorProfile::V);
+    if decode_with_meta(instruction_bits, &config).is_some()
+        || decode_with_meta(instruction_bits, &float).is_some()
+    {
+        return Some(Reason::VectorProfile);
+    }
+    (version + 1..=LATEST_VERSION)
+        .find(|version| {
+            let config = FactoryConfig::new::<u64>(*version).with_vector_crypto();
+            decode_with_meta(instruction_bits, &config).is_some()
+        })
+        .map(Reason::Version)
+        .or(Some(Reason::Unknown))
+}
+
+// Every word of the executable sections the deployed VM would reject, None if
+// elf_bytes is not an RV64 RISC-V ELF file
+pub fn check_elf(elf_bytes: &[u8], version: u32, profile: &IsaProfile) -> Option<Vec<Rejection>> {
+    let config = FactoryConfig::new::<u64>(version);
+    let mut rejections = Vec::new();
+    for section in executable_sections(elf_bytes)? {
+        let end = section.address + section.data.len() as u64;
+        for word in section.words(&config) {
+            // words cut off by the end of the section never decode
+            let reason = if word.pc + word.length() > end {
+                Some(Reason::Unknown)
+            } else {
+                check_word(word.instruction_bits, version, profile)
+            };
+            if let Some(reason) = reason {
+                rejections.push(Rejection {
+                    pc: word.pc,
+                    instruction_bits: word.instruction_bits,
+                    reason,
+                });
+            }
+        }
+    }
+    Some(rejections)
+}
+
+#[cfg(all(
+    test,
+    feature = "rvi",
+    feature = "rvm",
+    feature = "rvb",
+    feature = "rvc",
+    feature = "rvv",
+    feature = "rvvk"
+))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_word() {
+        let imc = IsaProfile::imc();
+        // mul a0, a0, a1
+        assert_eq!(check_word(0x02b50533, 0, &imc), None);
+        // c.unimp
+        assert_eq!(check_word(0x0000, 1, &imc), Some(Reason::Unknown));
+        // c.mv zero, a0
+        assert_eq!(check_word(0x802a, 0, &imc), Some(Reason::Version(1)));
+        assert_eq!(check_word(0x802a, 1, &imc), None);
+        // andn a0, a1, a2
+        assert_eq!(
+            check_word(0x40c5f533, 1, &imc),
+            Some(Reason::Extension("Zbb"))
+        );
+        assert_eq!(check_word(0x40c5f533, 1, &imc.clone().with_b()), None);
+        // vadd.vv v1, v2, v3
+        assert_eq!(
+            check_word(0x022180d7, 1, &imc),
+            Some(Reason::Extension("V"))
+        );
+        // vle64.v v1, (a0) and vclz.v v1, v2
+        let zve32x = IsaProfile::parse("imc,zve32x").unwrap();
+        assert_eq!(check_word(0x022180d7, 1, &zve32x), None);
+        assert_eq!(
+            check_word(0x02057087, 1, &zve32x),
+            Some(Reason::VectorProfile)
+        );
+        assert_eq!(
+            check_word(0x4a2620d7, 1, &zve32x),
+            Some(Reason::Extension("Zvbb"))
+        );
+        assert_eq!(
+            check_word(0x4a2620d7, 1, &IsaProfile::parse("v,zvk").unwrap()),
+            None
+        );
+        // vfadd.vv v8, v4, v12 needs F
+        assert_eq!(
+            check_word(0x02461457, 1, &zve32x),
+            Some(Reason::VectorProfile)
+        );
+        assert_eq!(
+            check_word(0x02461457, 1, &IsaProfile::parse("imc,v").unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_check_elf() {
+        let hints = include_bytes!("../tests/elf/hints.o");
+        let imc = IsaProfile::imc();
+        assert_eq!(check_elf(hints, 1, &imc), Some(vec![]));
+        assert_eq!(
+            check_elf(hints, 0, &imc),
+            Some(vec![Rejection {
+                pc: 0xe,
+                instruction_bits: 0x802a,
+                reason: Reason::Version(1),
+            }])
+        );
+        assert_eq!(check_elf(b"", 1, &imc), None);
+    }
+}
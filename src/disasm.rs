@@ -0,0 +1,612 @@
+// Prints decoded instructions the way GNU objdump does: ABI register names,
+// absolute branch targets and vtype mnemonics. Aliases such as `li` or `ret`
+// are used unless disabled, like `objdump -M no-aliases`.
+use super::i::FenceType;
+use super::opcodes as insts;
+use super::v::{Lmul, VFtype, VMemAddressing, VMemType, VType};
+use super::*;
+use ckb_vm::instructions::{extract_opcode, Itype, Rtype, Stype, Utype, VItype, VVtype, VXtype};
+
+#[cfg(feature = "rvc")]
+use super::rvc_decoder::{
+    addi16sp_immediate, addi4spn_uimmediate, b_immediate, fld_uimmediate, fldsp_uimmediate,
+    fsdsp_uimmediate, immediate, j_immediate, lwsp_uimmediate, sw_uimmediate, swsp_uimmediate,
+    uimmediate,
+};
+
+// opcode_name, generated by build.rs from the opcode definitions
+include!(concat!(env!("OUT_DIR"), "/opcode_names.rs"));
+
+const REGISTER_NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4",
+    "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+    "t5", "t6",
+];
+
+const FP_REGISTER_NAMES: [&str; 32] = [
+    "ft0", "ft1", "ft2", "ft3", "ft4", "ft5", "ft6", "ft7", "fs0", "fs1", "fa0", "fa1", "fa2",
+    "fa3", "fa4", "fa5", "fa6", "fa7", "fs2", "fs3", "fs4", "fs5", "fs6", "fs7", "fs8", "fs9",
+    "fs10", "fs11", "ft8", "ft9", "ft10", "ft11",
+];
+
+// Multiply-add instructions, which take their operands as vd, vs1, vs2
+const VECTOR_MULTIPLY_ADD: [&str; 20] = [
+    "VMACC", "VNMSAC", "VMADD", "VNMSUB", "VWMACC", "VWMACCU", "VWMACCSU", "VWMACCUS", "VFMACC",
+    "VFNMACC", "VFMSAC", "VFNMSAC", "VFMADD", "VFNMADD", "VFMSUB", "VFNMSUB", "VFWMACC",
+    "VFWNMACC", "VFWMSAC", "VFWNMSAC",
+];
+
+// .vi instructions whose immediate is unsigned
+const VECTOR_UIMM: [&str; 16] = [
+    "VSLL_VI",
+    "VSRL_VI",
+    "VSRA_VI",
+    "VSSRL_VI",
+    "VSSRA_VI",
+    "VNSRL_WI",
+    "VNSRA_WI",
+    "VNCLIP_WI",
+    "VNCLIPU_WI",
+    "VSLIDEUP_VI",
+    "VSLIDEDOWN_VI",
+    "VRGATHER_VI",
+    "VROR_VI",
+    "VWSLL_VI",
+    "VAESKF1_VI",
+    "VAESKF2_VI",
+];
+
+// Entries of the base templates, whose asm= columns operands follows
+const BASE_META: &[&[InstructionMeta]] = &[
+    #[cfg(feature = "rvi")]
+    &rvi_decoder::INSTRUCTION_META,
+    #[cfg(feature = "rvm")]
+    &rvm_decoder::INSTRUCTION_META,
+    #[cfg(feature = "rvb")]
+    &rvb_decoder::INSTRUCTION_META,
+];
+
+#[derive(Debug, Clone, Copy)]
+pub struct DisasmConfig {
+    aliases: bool,
+}
+
+impl DisasmConfig {
+    pub const fn new() -> Self {
+        Self { aliases: true }
+    }
+
+    // Print the base instruction instead of an alias, and compressed
+    // instructions by their c.* names
+    pub const fn without_aliases(mut self) -> Self {
+        self.aliases = false;
+        self
+    }
+}
+
+impl Default for DisasmConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn disassemble(instruction: Instruction, instruction_bits: u32, pc: u64) -> String {
+    disassemble_with_config(instruction, instruction_bits, pc, &DisasmConfig::new())
+}
+
+// instruction_bits are the bits instruction was decoded from, 2 byte words
+// in the lower half. They tell compressed and segment encodings apart.
+pub fn disassemble_with_config(
+    instruction: Instruction,
+    instruction_bits: u32,
+    pc: u64,
+    config: &DisasmConfig,
+) -> String {
+    let compressed = instruction_bits & 0x3 != 0x3;
+    #[cfg(feature = "rvc")]
+    {
+        if compressed && !config.aliases {
+            if let Some(text) = disassemble_compressed(instruction, instruction_bits, pc) {
+                return text;
+            }
+        }
+    }
+    let name = match opcode_name(extract_opcode(instruction)) {
+        Some(name) => name,
+        None if compressed => return format!(".2byte\t0x{:x}", instruction_bits & 0xffff),
+        None => return format!(".4byte\t0x{:x}", instruction_bits),
+    };
+    if config.aliases {
+        if let Some(text) = alias(instruction, compressed, pc) {
+            return text;
+        }
+    }
+    if name.starts_with('V') && !compressed {
+        return vector(instruction, instruction_bits, name);
+    }
+    join(&mnemonic(name), &operands(instruction, pc))
+}
+
+fn join(mnemonic: &str, operands: &str) -> String {
+    if operands.is_empty() {
+        mnemonic.to_string()
+    } else {
+        format!("{}\t{}", mnemonic, operands)
+    }
+}
+
+fn mnemonic(name: &str) -> String {
+    let mnemonic = match name {
+        "ADDUW" => "add.uw",
+        "SH1ADDUW" => "sh1add.uw",
+        "SH2ADDUW" => "sh2add.uw",
+        "SH3ADDUW" => "sh3add.uw",
+        "SLLIUW" => "slli.uw",
+        "ORCB" => "orc.b",
+        "SEXTB" => "sext.b",
+        "SEXTH" => "sext.h",
+        "ZEXTH" => "zext.h",
+        "FENCEI" => "fence.i",
+        // the templates keep the names of the 1.0 drafts
+        "VMANDNOT_MM" => "vmandn.mm",
+        "VMORNOT_MM" => "vmorn.mm",
+        _ => return name.to_lowercase().replace('_', "."),
+    };
+    mnemonic.to_string()
+}
+
+fn xreg(index: usize) -> &'static str {
+    REGISTER_NAMES[index]
+}
+
+fn freg(index: usize) -> &'static str {
+    FP_REGISTER_NAMES[index]
+}
+
+fn target(pc: u64, offset: i32) -> String {
+    format!("{:x}", pc.wrapping_add(offset as i64 as u64))
+}
+
+fn fence_set(set: u8) -> String {
+    let set: String = "iorw"
+        .chars()
+        .enumerate()
+        .filter(|(i, _)| set & (8 >> i) != 0)
+        .map(|(_, c)| c)
+        .collect();
+    if set.is_empty() {
+        "0".to_string()
+    } else {
+        set
+    }
+}
+
+// Operands by the asm= column of the base template entry of the opcode,
+// rd,rs1,rs2 when no enabled template has it
+fn operands(instruction: Instruction, pc: u64) -> String {
+    let opcode = extract_opcode(instruction);
+    let (asm, format) = BASE_META
+        .iter()
+        .flat_map(|metas| metas.iter())
+        .find(|meta| meta.opcode == opcode)
+        .map_or(("rd,rs1,rs2", "R"), |meta| (meta.asm, meta.format));
+    let mut text = String::new();
+    let mut rest = asm;
+    while let Some(start) = rest.find(|c: char| c.is_ascii_alphanumeric()) {
+        let end = rest[start..]
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .map_or(rest.len(), |end| start + end);
+        text.push_str(&rest[..start]);
+        text.push_str(&operand(&rest[start..end], format, instruction, pc));
+        rest = &rest[end..];
+    }
+    text + rest
+}
+
+// One asm= placeholder, read through the ckb-vm type the format is built as
+fn operand(field: &str, format: &str, instruction: Instruction, pc: u64) -> String {
+    let i = Itype(instruction);
+    let s = Stype(instruction);
+    let r = Rtype(instruction);
+    let u = Utype(instruction);
+    match (format, field) {
+        ("R", "rd") => xreg(r.rd()).to_string(),
+        ("R", "rs1") => xreg(r.rs1()).to_string(),
+        ("R", "rs2") => xreg(r.rs2()).to_string(),
+        ("I", "rd") => xreg(i.rd()).to_string(),
+        ("I", "rs1") => xreg(i.rs1()).to_string(),
+        ("I", "imm") => i.immediate_s().to_string(),
+        // shift amounts are printed in hex
+        ("I", "shamt") => format!("0x{:x}", i.immediate_u()),
+        ("I", "pred") => fence_set(FenceType(instruction).pred()),
+        ("I", "succ") => fence_set(FenceType(instruction).succ()),
+        ("S" | "B", "rs1") => xreg(s.rs1()).to_string(),
+        ("S" | "B", "rs2") => xreg(s.rs2()).to_string(),
+        ("S", "imm") => s.immediate_s().to_string(),
+        ("B", "target") => target(pc, s.immediate_s()),
+        ("U" | "J", "rd") => xreg(u.rd()).to_string(),
+        ("U", "imm20") => format!("0x{:x}", u.immediate_u() >> 12),
+        ("J", "target") => target(pc, u.immediate_s()),
+        // build/codegen.rs rejects any other placeholder
+        _ => field.to_string(),
+    }
+}
+
+// The aliases GNU objdump prints for base instructions. Vector instructions
+// are always printed as they are.
+fn alias(instruction: Instruction, compressed: bool, pc: u64) -> Option<String> {
+    let i = Itype(instruction);
+    let s = Stype(instruction);
+    let r = Rtype(instruction);
+    let text = match extract_opcode(instruction) {
+        insts::OP_ADDI if i.rd() == 0 && i.rs1() == 0 && i.immediate_s() == 0 => "nop".to_string(),
+        insts::OP_ADDI if i.rs1() == 0 => format!("li\t{},{}", xreg(i.rd()), i.immediate_s()),
+        insts::OP_ADDI if i.immediate_s() == 0 => format!("mv\t{},{}", xreg(i.rd()), xreg(i.rs1())),
+        // c.mv expands to add rd,zero,rs2
+        insts::OP_ADD if compressed && r.rs1() == 0 => {
+            format!("mv\t{},{}", xreg(r.rd()), xreg(r.rs2()))
+        }
+        insts::OP_ADDIW if i.immediate_s() == 0 => {
+            format!("sext.w\t{},{}", xreg(i.rd()), xreg(i.rs1()))
+        }
+        insts::OP_XORI if i.immediate_s() == -1 => {
+            format!("not\t{},{}", xreg(i.rd()), xreg(i.rs1()))
+        }
+        insts::OP_SUB if r.rs1() == 0 => format!("neg\t{},{}", xreg(r.rd()), xreg(r.rs2())),
+        insts::OP_SUBW if r.rs1() == 0 => format!("negw\t{},{}", xreg(r.rd()), xreg(r.rs2())),
+        insts::OP_SLTIU if i.immediate_s() == 1 => {
+            format!("seqz\t{},{}", xreg(i.rd()), xreg(i.rs1()))
+        }
+        insts::OP_SLTU if r.rs1() == 0 => format!("snez\t{},{}", xreg(r.rd()), xreg(r.rs2())),
+        insts::OP_SLT if r.rs2() == 0 => format!("sltz\t{},{}", xreg(r.rd()), xreg(r.rs1())),
+        insts::OP_SLT if r.rs1() == 0 => format!("sgtz\t{},{}", xreg(r.rd()), xreg(r.rs2())),
+        insts::OP_ADDUW if r.rs2() == 0 => {
+            format!("zext.w\t{},{}", xreg(r.rd()), xreg(r.rs1()))
+        }
+        insts::OP_BEQ if s.rs2() == 0 => {
+            format!("beqz\t{},{}", xreg(s.rs1()), target(pc, s.immediate_s()))
+        }
+        insts::OP_BNE if s.rs2() == 0 => {
+            format!("bnez\t{},{}", xreg(s.rs1()), target(pc, s.immediate_s()))
+        }
+        insts::OP_BLT if s.rs2() == 0 => {
+            format!("bltz\t{},{}", xreg(s.rs1()), target(pc, s.immediate_s()))
+        }
+        insts::OP_BLT if s.rs1() == 0 => {
+            format!("bgtz\t{},{}", xreg(s.rs2()), target(pc, s.immediate_s()))
+        }
+        insts::OP_BGE if s.rs1() == 0 => {
+            format!("blez\t{},{}", xreg(s.rs2()), target(pc, s.immediate_s()))
+        }
+        insts::OP_BGE if s.rs2() == 0 => {
+            format!("bgez\t{},{}", xreg(s.rs1()), target(pc, s.immediate_s()))
+        }
+        insts::OP_JAL => {
+            let u = Utype(instruction);
+            match u.rd() {
+                0 => format!("j\t{}", target(pc, u.immediate_s())),
+                1 => format!("jal\t{}", target(pc, u.immediate_s())),
+                _ => return None,
+            }
+        }
+        insts::OP_JALR => match (i.rd(), i.rs1(), i.immediate_s()) {
+            (0, 1, 0) => "ret".to_string(),
+            (0, rs1, 0) => format!("jr\t{}", xreg(rs1)),
+            (0, rs1, imm) => format!("jr\t{}({})", imm, xreg(rs1)),
+            (1, rs1, 0) => format!("jalr\t{}", xreg(rs1)),
+            (1, rs1, imm) => format!("jalr\t{}({})", imm, xreg(rs1)),
+            _ => return None,
+        },
+        insts::OP_FENCE => {
+            let fence = FenceType(instruction);
+            match (fence.fm(), fence.pred(), fence.succ()) {
+                (0, 0xf, 0xf) => "fence".to_string(),
+                (0x8, 0x3, 0x3) => "fence.tso".to_string(),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    Some(text)
+}
+
+fn vtype_name(vtype: VType) -> Option<String> {
+    let lmul = match vtype.lmul()? {
+        Lmul::Mf8 => "mf8",
+        Lmul::Mf4 => "mf4",
+        Lmul::Mf2 => "mf2",
+        Lmul::M1 => "m1",
+        Lmul::M2 => "m2",
+        Lmul::M4 => "m4",
+        Lmul::M8 => "m8",
+    };
+    Some(format!(
+        "e{},{},{},{}",
+        vtype.sew()?,
+        lmul,
+        if vtype.ta() { "ta" } else { "tu" },
+        if vtype.ma() { "ma" } else { "mu" }
+    ))
+}
+
+fn vector(instruction: Instruction, instruction_bits: u32, name: &str) -> String {
+    if matches!(instruction_bits & 0x7f, 0x07 | 0x27) {
+        return vector_memory(instruction, name);
+    }
+    let mnemonic = mnemonic(name);
+    let vsetvli = |avl: String| {
+        let i = Itype(instruction);
+        let vtype = VType::decode(u64::from(i.immediate_u()))
+            .ok()
+            .and_then(vtype_name)
+            .unwrap_or_else(|| i.immediate_u().to_string());
+        format!("{}\t{},{},{}", mnemonic, xreg(i.rd()), avl, vtype)
+    };
+    match extract_opcode(instruction) {
+        insts::OP_VSETVLI => return vsetvli(xreg(Itype(instruction).rs1()).to_string()),
+        insts::OP_VSETIVLI => return vsetvli(Itype(instruction).rs1().to_string()),
+        insts::OP_VSETVL => return join(&mnemonic, &operands(instruction, 0)),
+        insts::OP_VFMV_F_S => {
+            let r = Rtype(instruction);
+            return join(&mnemonic, &format!("{},v{}", freg(r.rd()), r.rs2()));
+        }
+        _ => (),
+    }
+
+    let (base, suffix) = name.split_once('_').unwrap_or((name, ""));
+    // (vd, vs2, vm, the vs1/rs1/imm operand)
+    let (vd, vs2, vm, operand) = match funct3(instruction_bits) {
+        0b011 => {
+            let i = VItype(instruction);
+            let imm = if VECTOR_UIMM.contains(&name) {
+                i.immediate_u().to_string()
+            } else {
+                (((i.immediate_u() as i32) << 27) >> 27).to_string()
+            };
+            (i.vd(), i.vs2(), i.vm(), imm)
+        }
+        0b100 | 0b110 => {
+            let v = VXtype(instruction);
+            (v.vd(), v.vs2(), v.vm(), xreg(v.rs1()).to_string())
+        }
+        0b101 => {
+            let v = VFtype(instruction);
+            (v.vd(), v.vs2(), v.vm(), freg(v.fs1()).to_string())
+        }
+        _ => {
+            let v = VVtype(instruction);
+            (v.vd(), v.vs2(), v.vm(), format!("v{}", v.vs1()))
+        }
+    };
+    let (vd_index, vd, vs2) = (vd, format!("v{}", vd), format!("v{}", vs2));
+    let mut text = match name {
+        "VMV_V_V" | "VMV_V_X" | "VMV_V_I" | "VMV_S_X" | "VFMV_V_F" | "VFMV_S_F" => {
+            format!("{},{}", vd, operand)
+        }
+        "VMV_X_S" | "VCPOP_M" | "VFIRST_M" => format!("{},{}", xreg(vd_index), vs2),
+        "VID_V" => vd,
+        _ if VECTOR_MULTIPLY_ADD.contains(&base) => format!("{},{},{}", vd, operand, vs2),
+        // the vs1 field selects the operation of these
+        _ if matches!(suffix, "V" | "W" | "M" | "VF2" | "VF4" | "VF8")
+            || name.starts_with("VFCVT")
+            || name.starts_with("VFWCVT")
+            || name.starts_with("VFNCVT")
+            || name.starts_with("VAES") && !name.starts_with("VAESKF")
+            || name == "VGMUL_VV" =>
+        {
+            format!("{},{}", vd, vs2)
+        }
+        _ => format!("{},{},{}", vd, vs2, operand),
+    };
+    if suffix.ends_with('M') && suffix.len() == 3 {
+        text += ",v0";
+    } else if !vm {
+        text += ",v0.t";
+    }
+    join(&mnemonic, &text)
+}
+
+// Unit-stride, strided and indexed entries decode all segment counts, the
+// mnemonic is put together from the fields
+fn vector_memory(instruction: Instruction, name: &str) -> String {
+    let inst = VMemType(instruction);
+    let mut mnemonic = mnemonic(name);
+    let whole_register = name.contains("RE") || name.ends_with("R_V");
+    if !whole_register && !name.starts_with("VLM") && !name.starts_with("VSM") {
+        // seg<n> goes before the EEW, mnemonics without one are kept as is
+        let digits = mnemonic.find(|c: char| c.is_ascii_digit());
+        if let Some(digits) = digits.filter(|_| inst.nf() != 0) {
+            let at = if mnemonic[..digits].ends_with("ei") {
+                digits - 2
+            } else {
+                digits.saturating_sub(1)
+            };
+            mnemonic.insert_str(at, &format!("seg{}", inst.nfields()));
+        }
+        if inst.addressing() == VMemAddressing::UnitStride && inst.lumop() == 0b10000 {
+            mnemonic.insert_str(mnemonic.len() - 2, "ff");
+        }
+    }
+    let mut text = format!("v{},({})", inst.vd(), xreg(inst.rs1()));
+    match inst.addressing() {
+        VMemAddressing::UnitStride => (),
+        VMemAddressing::Strided => text += &format!(",{}", xreg(inst.rs2())),
+        _ => text += &format!(",v{}", inst.rs2()),
+    }
+    if !inst.vm() && !whole_register {
+        text += ",v0.t";
+    }
+    join(&mnemonic, &text)
+}
+
+// c.* names and operands, told from the bits as hints decode to nop
+#[cfg(feature = "rvc")]
+fn disassemble_compressed(
+    instruction: Instruction,
+    instruction_bits: u32,
+    pc: u64,
+) -> Option<String> {
+    let bits = instruction_bits;
+    let rd = xreg(x(bits, 7, 5, 0) as usize);
+    let rs2 = xreg(x(bits, 2, 5, 0) as usize);
+    // rd'/rs1' and rs2', x8-x15
+    let rdc = xreg(8 + x(bits, 7, 3, 0) as usize);
+    let rs2c = xreg(8 + x(bits, 2, 3, 0) as usize);
+    let bit12 = x(bits, 12, 1, 0);
+    let (mnemonic, operands) = match (bits & 0x3, x(bits, 13, 3, 0)) {
+        (0, 0) => (
+            "c.addi4spn",
+            format!("{},sp,{}", rs2c, addi4spn_uimmediate(bits)),
+        ),
+        (0, 2) => ("c.lw", format!("{},{}({})", rs2c, sw_uimmediate(bits), rdc)),
+        (0, 3) => (
+            "c.ld",
+            format!("{},{}({})", rs2c, fld_uimmediate(bits), rdc),
+        ),
+        (0, 6) => ("c.sw", format!("{},{}({})", rs2c, sw_uimmediate(bits), rdc)),
+        (0, 7) => (
+            "c.sd",
+            format!("{},{}({})", rs2c, fld_uimmediate(bits), rdc),
+        ),
+        (1, 0) if bits & 0xffff == 0x1 => ("c.nop", String::new()),
+        (1, 0) => ("c.addi", format!("{},{}", rd, immediate(bits))),
+        (1, 1) if extract_opcode(instruction) == insts::OP_JAL => {
+            ("c.jal", target(pc, j_immediate(bits)))
+        }
+        (1, 1) => ("c.addiw", format!("{},{}", rd, immediate(bits))),
+        (1, 2) => ("c.li", format!("{},{}", rd, immediate(bits))),
+        (1, 3) if x(bits, 7, 5, 0) == 2 => {
+            ("c.addi16sp", format!("sp,{}", addi16sp_immediate(bits)))
+        }
+        (1, 3) => (
+            "c.lui",
+            format!("{},0x{:x}", rd, immediate(bits) as u32 & 0xfffff),
+        ),
+        (1, 4) => match (x(bits, 10, 2, 0), bit12, x(bits, 5, 2, 0)) {
+            (0, _, _) => ("c.srli", format!("{},0x{:x}", rdc, uimmediate(bits))),
+            (1, _, _) => ("c.srai", format!("{},0x{:x}", rdc, uimmediate(bits))),
+            (2, _, _) => ("c.andi", format!("{},{}", rdc, immediate(bits))),
+            (_, 0, 0) => ("c.sub", format!("{},{}", rdc, rs2c)),
+            (_, 0, 1) => ("c.xor", format!("{},{}", rdc, rs2c)),
+            (_, 0, 2) => ("c.or", format!("{},{}", rdc, rs2c)),
+            (_, 0, 3) => ("c.and", format!("{},{}", rdc, rs2c)),
+            (_, 1, 0) => ("c.subw", format!("{},{}", rdc, rs2c)),
+            (_, 1, 1) => ("c.addw", format!("{},{}", rdc, rs2c)),
+            _ => return None,
+        },
+        (1, 5) => ("c.j", target(pc, j_immediate(bits))),
+        (1, 6) => (
+            "c.beqz",
+            format!("{},{}", rdc, target(pc, b_immediate(bits))),
+        ),
+        (1, 7) => (
+            "c.bnez",
+            format!("{},{}", rdc, target(pc, b_immediate(bits))),
+        ),
+        (2, 0) => ("c.slli", format!("{},0x{:x}", rd, uimmediate(bits))),
+        (2, 2) => ("c.lwsp", format!("{},{}(sp)", rd, lwsp_uimmediate(bits))),
+        (2, 3) => ("c.ldsp", format!("{},{}(sp)", rd, fldsp_uimmediate(bits))),
+        (2, 4) => match (bit12, x(bits, 7, 5, 0), x(bits, 2, 5, 0)) {
+            (0, _, 0) => ("c.jr", rd.to_string()),
+            (0, _, _) => ("c.mv", format!("{},{}", rd, rs2)),
+            (_, 0, 0) => ("c.ebreak", String::new()),
+            (_, _, 0) => ("c.jalr", rd.to_string()),
+            _ => ("c.add", format!("{},{}", rd, rs2)),
+        },
+        (2, 6) => ("c.swsp", format!("{},{}(sp)", rs2, swsp_uimmediate(bits))),
+        (2, 7) => ("c.sdsp", format!("{},{}(sp)", rs2, fsdsp_uimmediate(bits))),
+        _ => return None,
+    };
+    Some(join(mnemonic, &operands))
+}
+
+#[cfg(all(
+    test,
+    feature = "rvi",
+    feature = "rvm",
+    feature = "rvb",
+    feature = "rvc",
+    feature = "rvv"
+))]
+mod tests {
+    use super::*;
+
+    fn decode(instruction_bits: u32) -> Option<Instruction> {
+        let config = FactoryConfig::new::<u64>(1).with_vector_profile(VectorProfile::V);
+        if instruction_bits & 0x3 != 0x3 {
+            return rvc_decoder::factory_with_config(instruction_bits, &config);
+        }
+        rvi_decoder::factory_with_config(instruction_bits, &config)
+            .or_else(|| rvm_decoder::factory_with_config(instruction_bits, &config))
+            .or_else(|| rvb_decoder::factory_with_config(instruction_bits, &config))
+            .or_else(|| rvv_decoder::factory_with_config(instruction_bits, &config))
+    }
+
+    // Lines are `pc:\tword\ttext` as objdump prints them
+    fn check_golden(golden: &str) {
+        let config = DisasmConfig::new().without_aliases();
+        for line in golden.lines() {
+            let mut fields = line.splitn(3, '\t');
+            let pc = fields.next().unwrap().trim().trim_end_matches(':');
+            let pc = u64::from_str_radix(pc, 16).unwrap();
+            let instruction_bits = u32::from_str_radix(fields.next().unwrap().trim(), 16).unwrap();
+            let expected = fields.next().unwrap();
+            let instruction = decode(instruction_bits)
+                .unwrap_or_else(|| panic!("{:x} does not decode", instruction_bits));
+            assert_eq!(
+                disassemble_with_config(instruction, instruction_bits, pc, &config),
+                expected,
+                "at {:x}",
+                pc
+            );
+        }
+    }
+
+    #[test]
+    fn test_golden_rv64() {
+        check_golden(include_str!("../tests/golden/rv64.txt"));
+    }
+
+    #[test]
+    fn test_golden_rvv() {
+        check_golden(include_str!("../tests/golden/rvv.txt"));
+    }
+
+    // vlseg2e8.v, expected in the GNU objdump syntax of vlseg2e32.v in
+    // tests/golden/rvv.txt
+    #[test]
+    fn test_vector_memory() {
+        let instruction = decode(0x22050407).unwrap();
+        assert_eq!(vector_memory(instruction, "VLE8_V"), "vlseg2e8.v\tv8,(a0)");
+        // no EEW to put seg2 before
+        assert_eq!(vector_memory(instruction, "VLE_V"), "vle.v\tv8,(a0)");
+    }
+
+    #[test]
+    fn test_aliases() {
+        let cases = [
+            (0x00000013, 0, "nop"),
+            (0xffb00513, 0, "li\ta0,-5"),
+            (0x00058513, 0, "mv\ta0,a1"),
+            (0x00008067, 0, "ret"),
+            (0x00050067, 0, "jr\ta0"),
+            (0x000500e7, 0, "jalr\ta0"),
+            (0xffdff06f, 0x14, "j\t10"),
+            (0x008000ef, 0x10, "jal\t18"),
+            (0xfe0418e3, 0x2c, "bnez\ts0,1c"),
+            (0x80c05063, 0x34, "blez\ta2,fffffffffffff034"),
+            (0x0ff0000f, 0, "fence"),
+            (0x0005851b, 0, "sext.w\ta0,a1"),
+            (0x0805853b, 0, "zext.w\ta0,a1"),
+            (0x852e, 0, "mv\ta0,a1"),
+            (0x8082, 0, "ret"),
+            (0x557d, 0, "li\ta0,-1"),
+            (0x157d, 0, "addi\ta0,a0,-1"),
+            (0x6685, 0, "lui\ta3,0x1"),
+        ];
+        for (instruction_bits, pc, expected) in cases.iter() {
+            let instruction = decode(*instruction_bits).unwrap();
+            assert_eq!(disassemble(instruction, *instruction_bits, *pc), *expected);
+        }
+    }
+}
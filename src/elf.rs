@@ -0,0 +1,223 @@
+// Walks the executable sections of an RV64 ELF file through the decoders of
+// the enabled extensions, for the ckb-decode binary.
+use super::*;
+use goblin::elf::header::{EM_RISCV, ET_REL};
+use goblin::elf::section_header::SHT_NOBITS;
+use goblin::elf::sym::{STT_FILE, STT_SECTION};
+use std::collections::BTreeMap;
+
+type Factory = fn(u32, &FactoryConfig) -> Option<Instruction>;
+
+// Decoders for 4 byte words along with their template metadata, tried in
+// this order
+const DECODERS: &[(Factory, &[InstructionMeta])] = &[
+    #[cfg(feature = "rvi")]
+    (
+        rvi_decoder::factory_with_config,
+        &rvi_decoder::INSTRUCTION_META,
+    ),
+    #[cfg(feature = "rvm")]
+    (
+        rvm_decoder::factory_with_config,
+        &rvm_decoder::INSTRUCTION_META,
+    ),
+    #[cfg(feature = "rvb")]
+    (
+        rvb_decoder::factory_with_config,
+        &rvb_decoder::INSTRUCTION_META,
+    ),
+    #[cfg(feature = "rvv")]
+    (
+        rvv_decoder::factory_with_config,
+        &rvv_decoder::INSTRUCTION_META,
+    ),
+    #[cfg(feature = "rvvk")]
+    (
+        rvvk_decoder::factory_with_config,
+        &rvvk_decoder::INSTRUCTION_META,
+    ),
+];
+
+// Decodes a word with whichever decoder claims it, 2 byte words go to
+// rvc_decoder alone
+pub fn decode(instruction_bits: u32, config: &FactoryConfig) -> Option<Instruction> {
+    decode_with_meta(instruction_bits, config).map(|(instruction, _)| instruction)
+}
+
+// Like decode, along with the template entry the word matched
+pub fn decode_with_meta(
+    instruction_bits: u32,
+    config: &FactoryConfig,
+) -> Option<(Instruction, &'static InstructionMeta)> {
+    let decoders: &[(Factory, &'static [InstructionMeta])] = if instruction_bits & 0x3 != 0x3 {
+        #[cfg(feature = "rvc")]
+        {
+            &[(
+                rvc_decoder::factory_with_config,
+                &rvc_decoder::INSTRUCTION_META,
+            )]
+        }
+        #[cfg(not(feature = "rvc"))]
+        {
+            &[]
+        }
+    } else {
+        DECODERS
+    };
+    decoders.iter().find_map(|(factory, metas)| {
+        let instruction = factory(instruction_bits, config)?;
+        // e.g. C.EBREAK also matches the masks of C.ADD and C.JALR
+        let meta = metas
+            .iter()
+            .filter(|meta| instruction_bits & meta.mask == meta.match_bits)
+            .filter(|meta| meta.xlen.supports(config))
+            .max_by_key(|meta| meta.mask.count_ones())?;
+        Some((instruction, meta))
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Word {
+    pub pc: u64,
+    // 2 byte words in the lower half, a truncated word at the end of a
+    // section holds the bytes left
+    pub instruction_bits: u32,
+    pub instruction: Option<Instruction>,
+}
+
+impl Word {
+    pub fn length(&self) -> u64 {
+        if self.instruction_bits & 0x3 != 0x3 {
+            2
+        } else {
+            4
+        }
+    }
+}
+
+pub struct Section<'a> {
+    pub name: String,
+    pub address: u64,
+    pub data: &'a [u8],
+    // Names of the symbols defined in the section by address
+    pub symbols: BTreeMap<u64, Vec<String>>,
+}
+
+impl<'a> Section<'a> {
+    // The word at pc, 2 byte words in the lower half. None outside of the
+    // section or when the word is cut off by its end.
+    pub fn instruction_bits_at(&self, pc: u64) -> Option<u32> {
+        let offset = usize::try_from(pc.checked_sub(self.address)?).ok()?;
+        let length = if self.data.get(offset)? & 0x3 != 0x3 {
+            2
+        } else {
+            4
+        };
+        let mut bytes = [0u8; 4];
+        bytes[..length].copy_from_slice(self.data.get(offset..offset + length)?);
+        Some(u32::from_le_bytes(bytes))
+    }
+
+    pub fn words(&self, config: &FactoryConfig) -> Vec<Word> {
+        let mut words = Vec::new();
+        let mut offset = 0;
+        while offset < self.data.len() {
+            let length = if self.data[offset] & 0x3 != 0x3 { 2 } else { 4 };
+            let end = (offset + length).min(self.data.len());
+            let mut bytes = [0u8; 4];
+            bytes[..end - offset].copy_from_slice(&self.data[offset..end]);
+            let instruction_bits = u32::from_le_bytes(bytes);
+            words.push(Word {
+                pc: self.address + offset as u64,
+                instruction_bits,
+                instruction: if end - offset == length {
+                    decode(instruction_bits, config)
+                } else {
+                    None
+                },
+            });
+            offset += length;
+        }
+        words
+    }
+}
+
+// The executable sections of an RV64 RISC-V ELF file, None if it is not one
+pub fn executable_sections(elf_bytes: &[u8]) -> Option<Vec<Section<'_>>> {
+    let elf = goblin::elf::Elf::parse(elf_bytes).ok()?;
+    if !elf.is_64 || elf.header.e_machine != EM_RISCV {
+        return None;
+    }
+    let mut sections = Vec::new();
+    for (index, header) in elf.section_headers.iter().enumerate() {
+        if !header.is_executable() || header.sh_type == SHT_NOBITS {
+            continue;
+        }
+        let symbols = elf
+            .syms
+            .iter()
+            .filter(|sym| sym.st_shndx == index && sym.st_name != 0)
+            .filter(|sym| sym.st_type() != STT_SECTION && sym.st_type() != STT_FILE)
+            .filter_map(|sym| Some((sym.st_value, elf.strtab.get_at(sym.st_name)?)))
+            .fold(BTreeMap::new(), |mut symbols, (value, name)| {
+                // st_value is relative to the section in relocatable files
+                let address = if elf.header.e_type == ET_REL {
+                    header.sh_addr + value
+                } else {
+                    value
+                };
+                symbols
+                    .entry(address)
+                    .or_insert_with(Vec::new)
+                    .push(name.to_string());
+                symbols
+            });
+        sections.push(Section {
+            name: elf.shdr_strtab.get_at(header.sh_name)?.to_string(),
+            address: header.sh_addr,
+            data: elf_bytes.get(header.file_range()?)?,
+            symbols,
+        });
+    }
+    Some(sections)
+}
+
+#[cfg(all(test, feature = "rvi", feature = "rvm", feature = "rvc"))]
+mod tests {
+    use super::*;
+
+    // See tests/elf/hints.s
+    const HINTS: &[u8] = include_bytes!("../tests/elf/hints.o");
+
+    #[test]
+    fn test_executable_sections() {
+        assert!(executable_sections(b"\x7fELF").is_none());
+        let sections = executable_sections(HINTS).unwrap();
+        assert_eq!(sections.len(), 1);
+        let text = &sections[0];
+        assert_eq!(text.name, ".text");
+        assert_eq!(text.symbols[&0x0], ["_start"]);
+        assert_eq!(text.symbols[&0xe], ["hint"]);
+
+        let words = text.words(&FactoryConfig::new::<u64>(1));
+        let pcs: Vec<u64> = words.iter().map(|word| word.pc).collect();
+        assert_eq!(pcs, [0x0, 0x2, 0x4, 0x8, 0xc, 0xe, 0x10]);
+        assert!(words.iter().all(|word| word.instruction.is_some()));
+        // The c.mv HINT only decodes from version 1 on
+        let rejected: Vec<u64> = text
+            .words(&FactoryConfig::new::<u64>(0))
+            .iter()
+            .filter(|word| word.instruction.is_none())
+            .map(|word| word.pc)
+            .collect();
+        assert_eq!(rejected, [0xe]);
+    }
+
+    // c.addiw a0, 1 from llvm-mc -triple=riscv64 -mattr=+c, C.JAL on RV32
+    #[test]
+    fn test_decode_with_meta() {
+        let name = |config: &FactoryConfig| decode_with_meta(0x2505, config).unwrap().1.name;
+        assert_eq!(name(&FactoryConfig::new::<u64>(1)), "ADDIW");
+        assert_eq!(name(&FactoryConfig::new::<u32>(1)), "JAL");
+    }
+}
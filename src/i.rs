@@ -7,7 +7,7 @@ use ckb_vm::instructions::insts;
 use ckb_vm::instructions::{Instruction, Itype, Rtype};
 
 #[derive(Debug, Clone, Copy)]
-pub struct FenceType(Instruction);
+pub struct FenceType(pub Instruction);
 
 impl FenceType {
     pub fn new(fm: u8, pred: u8, succ: u8) -> Self {
@@ -58,7 +58,7 @@ pub fn is_1f_builder(
     )
 }
 
-pub fn fencei_builder(
+pub fn fence_builder(
     instruction_bits: u32,
     _: insts::InstructionOpcode,
     _: &FactoryConfig,
@@ -72,3 +72,74 @@ pub fn fencei_builder(
         .0,
     )
 }
+
+pub fn is_alu_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = Itype(instruction);
+    Some(match_bits | encode_rd(i.rd()) | encode_rs1(i.rs1()) | x(i.immediate_u(), 0, 6, 20))
+}
+
+pub fn is_1f_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = Itype(instruction);
+    Some(match_bits | encode_rd(i.rd()) | encode_rs1(i.rs1()) | x(i.immediate_u(), 0, 5, 20))
+}
+
+pub fn fence_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let fence = FenceType(instruction);
+    Some(
+        match_bits
+            | x(u32::from(fence.fm()), 0, 4, 28)
+            | x(u32::from(fence.pred()), 0, 4, 24)
+            | x(u32::from(fence.succ()), 0, 4, 20),
+    )
+}
+
+#[cfg(all(test, feature = "rvi"))]
+mod tests {
+    use super::*;
+    use ckb_vm::instructions::{
+        blank_instruction, extract_opcode, set_instruction_length_4, Utype,
+    };
+
+    fn decode(instruction_bits: u32) -> Instruction {
+        rvi_decoder::factory_with_config(instruction_bits, &FactoryConfig::new::<u64>(1)).unwrap()
+    }
+
+    // Words from llvm-mc -triple=riscv64 -show-encoding
+    #[test]
+    fn test_jumps() {
+        // jal ra, -2048 and jal t0, 524286: J-type offsets, see uj_builder
+        for (instruction_bits, rd, offset) in [(0x801ff0ef, 1, -2048), (0x7ff7f2ef, 5, 524286)] {
+            let jal = Utype(decode(instruction_bits));
+            assert_eq!(extract_opcode(jal.0), insts::OP_JAL);
+            assert_eq!((jal.rd(), jal.immediate_s()), (rd, offset));
+        }
+        // jalr t0, -4(a0) and jalr ra, 2047(sp): I-type, see is_builder
+        for (instruction_bits, rd, rs1, offset) in
+            [(0xffc502e7, 5, 10, -4), (0x7ff100e7, 1, 2, 2047)]
+        {
+            let jalr = Itype(decode(instruction_bits));
+            assert_eq!(extract_opcode(jalr.0), insts::OP_JALR);
+            assert_eq!(
+                (jalr.rd(), jalr.rs1(), jalr.immediate_s()),
+                (rd, rs1, offset)
+            );
+        }
+    }
+
+    #[test]
+    fn test_fences() {
+        // fence rw, w and fence.tso keep fm, pred and succ
+        for (instruction_bits, fm, pred, succ) in
+            [(0x0310000f, 0, 0x3, 0x1), (0x8330000f, 0x8, 0x3, 0x3)]
+        {
+            let fence = FenceType(decode(instruction_bits));
+            assert_eq!(extract_opcode(fence.0), insts::OP_FENCE);
+            assert_eq!((fence.fm(), fence.pred(), fence.succ()), (fm, pred, succ));
+        }
+        // fence.i has no operands
+        assert_eq!(
+            decode(0x0000100f),
+            set_instruction_length_4(blank_instruction(insts::OP_FENCEI))
+        );
+    }
+}
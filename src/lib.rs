@@ -1,16 +1,35 @@
-include!("./mod.rs");
+include!(concat!(env!("OUT_DIR"), "/mod.rs"));
 pub mod b;
 pub mod common;
+#[cfg(feature = "elf")]
+pub mod compat;
+pub mod disasm;
+#[cfg(feature = "elf")]
+pub mod elf;
 pub mod i;
+#[cfg(feature = "rvc")]
 pub mod c;
+pub mod opcodes;
+pub mod operands;
+pub mod perfect_hash;
+#[cfg(feature = "elf")]
+pub mod stats;
+pub mod table;
 pub mod v;
 
+// Builders for the generated decoders, only imported when one uses them
+#[cfg(feature = "rvb")]
 use b::*;
 use common::*;
+#[cfg(feature = "rvi")]
 use i::*;
+#[cfg(feature = "rvc")]
 use c::*;
 use ckb_vm::instructions::insts;
+#[cfg(any(feature = "rvv", feature = "rvvk"))]
 use v::*;
+use operands::Operand;
+use v::VectorProfile;
 
 use ckb_vm::instructions::Instruction;
 use ckb_vm::Register;
@@ -22,16 +41,23 @@ pub type OpcodeBuilder = fn(
     config: &FactoryConfig,
 ) -> Option<Instruction>;
 
+// Inverse of an OpcodeBuilder, puts the operands of instruction into the
+// match bits of its entry. Whether the word decodes back to instruction is
+// left to encode_with_config of the generated decoders.
+pub type OpcodeEncoder = fn(instruction: Instruction, match_bits: u32) -> Option<u32>;
 
 // TODO solve this, can we be generic?
 // To be generic, means the instruction_lists needs to be initilized at runtime
 // This is because function pointer cannot take generic type.
 // This means overhead at startup for each factory, but can we be lazy_static?
+#[derive(Debug, Clone, Copy)]
 pub struct FactoryConfig {
     rv32: bool,
     rv64: bool,
     version: u32,
     shift_masks: u8,
+    vector_profile: Option<VectorProfile>,
+    vector_crypto: bool,
 }
 
 impl FactoryConfig {
@@ -41,6 +67,41 @@ impl FactoryConfig {
             rv64: R::BITS == 64,
             version,
             shift_masks: R::SHIFT_MASK,
+            vector_profile: None,
+            vector_crypto: false,
+        }
+    }
+
+    // Reject vector encodings the profile does not support, e.g. SEW=64
+    // under Zve32x or float operations without F. Without a profile float
+    // operations are rejected too, ckb-vm cannot execute them.
+    pub const fn with_vector_profile(mut self, profile: VectorProfile) -> Self {
+        self.vector_profile = Some(profile);
+        self
+    }
+
+    // Decode the vector crypto tables (Zvbb, Zvbc, Zvkg, Zvkned, Zvknh),
+    // they are rejected otherwise.
+    pub const fn with_vector_crypto(mut self) -> Self {
+        self.vector_crypto = true;
+        self
+    }
+}
+
+// Register width an instruction is defined for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Xlen {
+    Both,
+    Rv32,
+    Rv64,
+}
+
+impl Xlen {
+    pub const fn supports(self, config: &FactoryConfig) -> bool {
+        match self {
+            Xlen::Both => true,
+            Xlen::Rv32 => config.rv32,
+            Xlen::Rv64 => config.rv64,
         }
     }
 }
@@ -50,6 +111,26 @@ pub struct InstructionInfo {
     match_bits: u32,
     opcode: insts::InstructionOpcode,
     builder: OpcodeBuilder,
+    xlen: Xlen,
+    min_version: u32,
+    // (mask, min_version) of the entries merged into this one
+    versions: &'static [(u32, u32)],
+}
+
+// Of the merged entries matching instruction_bits, the one with the most mask
+// bits decides, e.g. C.EBREAK over the C.ADD HINTs. Ties go to the lower
+// version.
+fn merged_min_version(
+    versions: &[(u32, u32)],
+    match_bits: u32,
+    instruction_bits: u32,
+    min_version: u32,
+) -> u32 {
+    versions
+        .iter()
+        .filter(|(mask, _)| instruction_bits & mask == match_bits)
+        .max_by_key(|(mask, version)| (mask.count_ones(), std::cmp::Reverse(*version)))
+        .map_or(min_version, |(_, version)| *version)
 }
 
 impl InstructionInfo {
@@ -64,10 +145,60 @@ impl InstructionInfo {
             match_bits,
             opcode,
             builder,
+            xlen: Xlen::Both,
+            min_version: 0,
+            versions: &[],
         }
     }
 
+    pub const fn with_xlen(mut self, xlen: Xlen) -> Self {
+        self.xlen = xlen;
+        self
+    }
+
+    pub const fn with_min_version(mut self, min_version: u32) -> Self {
+        self.min_version = min_version;
+        self
+    }
+
+    pub const fn with_versions(mut self, versions: &'static [(u32, u32)]) -> Self {
+        self.versions = versions;
+        self
+    }
+
     pub const fn get_match_bits(&self) -> u32 {
         self.match_bits
     }
+
+    // Checked by the factories before the builder runs
+    pub fn available(&self, instruction_bits: u32, config: &FactoryConfig) -> bool {
+        self.xlen.supports(config)
+            && config.version
+                >= merged_min_version(
+                    self.versions,
+                    self.match_bits,
+                    instruction_bits,
+                    self.min_version,
+                )
+    }
+}
+
+// What a template line says about an instruction, exported by every
+// generated decoder as INSTRUCTION_META.
+#[derive(Debug, Clone, Copy)]
+pub struct InstructionMeta {
+    pub name: &'static str,
+    pub opcode: insts::InstructionOpcode,
+    pub mask: u32,
+    pub match_bits: u32,
+    pub format: &'static str,
+    pub xlen: Xlen,
+    pub min_version: u32,
+    pub extension: &'static str,
+    // Operands as objdump prints them, e.g. "rd,imm(rs1)", see disasm::operands
+    pub asm: &'static str,
+    pub encoder: OpcodeEncoder,
+    // Register operands, see operands::def_use
+    pub reads: &'static [Operand],
+    pub writes: &'static [Operand],
 }
@@ -0,0 +1,148 @@
+// Opcodes of instructions ckb-vm does not define (yet). They share the
+// InstructionOpcode space with ckb-vm, starting from 0x1000 which is well
+// above its own opcodes, so templates can refer to both through `insts::OP_*`.
+// They are decode-only: ckb-vm cannot execute them, so the factories only
+// return them when asked to, with FactoryConfig::with_vector_profile (float)
+// or FactoryConfig::with_vector_crypto.
+pub use ckb_vm::instructions::insts::*;
+
+// RVV floating-point
+pub const OP_VFADD_VF: InstructionOpcode = 0x1000;
+pub const OP_VFADD_VV: InstructionOpcode = 0x1001;
+pub const OP_VFCLASS_V: InstructionOpcode = 0x1002;
+pub const OP_VFCVT_F_X_V: InstructionOpcode = 0x1003;
+pub const OP_VFCVT_F_XU_V: InstructionOpcode = 0x1004;
+pub const OP_VFCVT_RTZ_X_F_V: InstructionOpcode = 0x1005;
+pub const OP_VFCVT_RTZ_XU_F_V: InstructionOpcode = 0x1006;
+pub const OP_VFCVT_X_F_V: InstructionOpcode = 0x1007;
+pub const OP_VFCVT_XU_F_V: InstructionOpcode = 0x1008;
+pub const OP_VFDIV_VF: InstructionOpcode = 0x1009;
+pub const OP_VFDIV_VV: InstructionOpcode = 0x100a;
+pub const OP_VFMACC_VF: InstructionOpcode = 0x100b;
+pub const OP_VFMACC_VV: InstructionOpcode = 0x100c;
+pub const OP_VFMADD_VF: InstructionOpcode = 0x100d;
+pub const OP_VFMADD_VV: InstructionOpcode = 0x100e;
+pub const OP_VFMAX_VF: InstructionOpcode = 0x100f;
+pub const OP_VFMAX_VV: InstructionOpcode = 0x1010;
+pub const OP_VFMERGE_VFM: InstructionOpcode = 0x1011;
+pub const OP_VFMIN_VF: InstructionOpcode = 0x1012;
+pub const OP_VFMIN_VV: InstructionOpcode = 0x1013;
+pub const OP_VFMSAC_VF: InstructionOpcode = 0x1014;
+pub const OP_VFMSAC_VV: InstructionOpcode = 0x1015;
+pub const OP_VFMSUB_VF: InstructionOpcode = 0x1016;
+pub const OP_VFMSUB_VV: InstructionOpcode = 0x1017;
+pub const OP_VFMUL_VF: InstructionOpcode = 0x1018;
+pub const OP_VFMUL_VV: InstructionOpcode = 0x1019;
+pub const OP_VFMV_F_S: InstructionOpcode = 0x101a;
+pub const OP_VFMV_S_F: InstructionOpcode = 0x101b;
+pub const OP_VFMV_V_F: InstructionOpcode = 0x101c;
+pub const OP_VFNCVT_F_F_W: InstructionOpcode = 0x101d;
+pub const OP_VFNCVT_F_X_W: InstructionOpcode = 0x101e;
+pub const OP_VFNCVT_F_XU_W: InstructionOpcode = 0x101f;
+pub const OP_VFNCVT_ROD_F_F_W: InstructionOpcode = 0x1020;
+pub const OP_VFNCVT_RTZ_X_F_W: InstructionOpcode = 0x1021;
+pub const OP_VFNCVT_RTZ_XU_F_W: InstructionOpcode = 0x1022;
+pub const OP_VFNCVT_X_F_W: InstructionOpcode = 0x1023;
+pub const OP_VFNCVT_XU_F_W: InstructionOpcode = 0x1024;
+pub const OP_VFNMACC_VF: InstructionOpcode = 0x1025;
+pub const OP_VFNMACC_VV: InstructionOpcode = 0x1026;
+pub const OP_VFNMADD_VF: InstructionOpcode = 0x1027;
+pub const OP_VFNMADD_VV: InstructionOpcode = 0x1028;
+pub const OP_VFNMSAC_VF: InstructionOpcode = 0x1029;
+pub const OP_VFNMSAC_VV: InstructionOpcode = 0x102a;
+pub const OP_VFNMSUB_VF: InstructionOpcode = 0x102b;
+pub const OP_VFNMSUB_VV: InstructionOpcode = 0x102c;
+pub const OP_VFRDIV_VF: InstructionOpcode = 0x102d;
+pub const OP_VFREC7_V: InstructionOpcode = 0x102e;
+pub const OP_VFREDMAX_VS: InstructionOpcode = 0x102f;
+pub const OP_VFREDMIN_VS: InstructionOpcode = 0x1030;
+pub const OP_VFREDOSUM_VS: InstructionOpcode = 0x1031;
+pub const OP_VFREDUSUM_VS: InstructionOpcode = 0x1032;
+pub const OP_VFRSQRT7_V: InstructionOpcode = 0x1033;
+pub const OP_VFRSUB_VF: InstructionOpcode = 0x1034;
+pub const OP_VFSGNJ_VF: InstructionOpcode = 0x1035;
+pub const OP_VFSGNJ_VV: InstructionOpcode = 0x1036;
+pub const OP_VFSGNJN_VF: InstructionOpcode = 0x1037;
+pub const OP_VFSGNJN_VV: InstructionOpcode = 0x1038;
+pub const OP_VFSGNJX_VF: InstructionOpcode = 0x1039;
+pub const OP_VFSGNJX_VV: InstructionOpcode = 0x103a;
+pub const OP_VFSLIDE1DOWN_VF: InstructionOpcode = 0x103b;
+pub const OP_VFSLIDE1UP_VF: InstructionOpcode = 0x103c;
+pub const OP_VFSQRT_V: InstructionOpcode = 0x103d;
+pub const OP_VFSUB_VF: InstructionOpcode = 0x103e;
+pub const OP_VFSUB_VV: InstructionOpcode = 0x103f;
+pub const OP_VFWADD_VF: InstructionOpcode = 0x1040;
+pub const OP_VFWADD_VV: InstructionOpcode = 0x1041;
+pub const OP_VFWADD_WF: InstructionOpcode = 0x1042;
+pub const OP_VFWADD_WV: InstructionOpcode = 0x1043;
+pub const OP_VFWCVT_F_F_V: InstructionOpcode = 0x1044;
+pub const OP_VFWCVT_F_X_V: InstructionOpcode = 0x1045;
+pub const OP_VFWCVT_F_XU_V: InstructionOpcode = 0x1046;
+pub const OP_VFWCVT_RTZ_X_F_V: InstructionOpcode = 0x1047;
+pub const OP_VFWCVT_RTZ_XU_F_V: InstructionOpcode = 0x1048;
+pub const OP_VFWCVT_X_F_V: InstructionOpcode = 0x1049;
+pub const OP_VFWCVT_XU_F_V: InstructionOpcode = 0x104a;
+pub const OP_VFWMACC_VF: InstructionOpcode = 0x104b;
+pub const OP_VFWMACC_VV: InstructionOpcode = 0x104c;
+pub const OP_VFWMSAC_VF: InstructionOpcode = 0x104d;
+pub const OP_VFWMSAC_VV: InstructionOpcode = 0x104e;
+pub const OP_VFWMUL_VF: InstructionOpcode = 0x104f;
+pub const OP_VFWMUL_VV: InstructionOpcode = 0x1050;
+pub const OP_VFWNMACC_VF: InstructionOpcode = 0x1051;
+pub const OP_VFWNMACC_VV: InstructionOpcode = 0x1052;
+pub const OP_VFWNMSAC_VF: InstructionOpcode = 0x1053;
+pub const OP_VFWNMSAC_VV: InstructionOpcode = 0x1054;
+pub const OP_VFWREDOSUM_VS: InstructionOpcode = 0x1055;
+pub const OP_VFWREDUSUM_VS: InstructionOpcode = 0x1056;
+pub const OP_VFWSUB_VF: InstructionOpcode = 0x1057;
+pub const OP_VFWSUB_VV: InstructionOpcode = 0x1058;
+pub const OP_VFWSUB_WF: InstructionOpcode = 0x1059;
+pub const OP_VFWSUB_WV: InstructionOpcode = 0x105a;
+pub const OP_VMFEQ_VF: InstructionOpcode = 0x105b;
+pub const OP_VMFEQ_VV: InstructionOpcode = 0x105c;
+pub const OP_VMFGE_VF: InstructionOpcode = 0x105d;
+pub const OP_VMFGT_VF: InstructionOpcode = 0x105e;
+pub const OP_VMFLE_VF: InstructionOpcode = 0x105f;
+pub const OP_VMFLE_VV: InstructionOpcode = 0x1060;
+pub const OP_VMFLT_VF: InstructionOpcode = 0x1061;
+pub const OP_VMFLT_VV: InstructionOpcode = 0x1062;
+pub const OP_VMFNE_VF: InstructionOpcode = 0x1063;
+pub const OP_VMFNE_VV: InstructionOpcode = 0x1064;
+
+// Vector crypto (Zvbb, Zvbc, Zvkg, Zvkned, Zvknh)
+pub const OP_VAESDF_VS: InstructionOpcode = 0x1100;
+pub const OP_VAESDF_VV: InstructionOpcode = 0x1101;
+pub const OP_VAESDM_VS: InstructionOpcode = 0x1102;
+pub const OP_VAESDM_VV: InstructionOpcode = 0x1103;
+pub const OP_VAESEF_VS: InstructionOpcode = 0x1104;
+pub const OP_VAESEF_VV: InstructionOpcode = 0x1105;
+pub const OP_VAESEM_VS: InstructionOpcode = 0x1106;
+pub const OP_VAESEM_VV: InstructionOpcode = 0x1107;
+pub const OP_VAESKF1_VI: InstructionOpcode = 0x1108;
+pub const OP_VAESKF2_VI: InstructionOpcode = 0x1109;
+pub const OP_VAESZ_VS: InstructionOpcode = 0x110a;
+pub const OP_VANDN_VV: InstructionOpcode = 0x110b;
+pub const OP_VANDN_VX: InstructionOpcode = 0x110c;
+pub const OP_VBREV_V: InstructionOpcode = 0x110d;
+pub const OP_VBREV8_V: InstructionOpcode = 0x110e;
+pub const OP_VCLMUL_VV: InstructionOpcode = 0x110f;
+pub const OP_VCLMUL_VX: InstructionOpcode = 0x1110;
+pub const OP_VCLMULH_VV: InstructionOpcode = 0x1111;
+pub const OP_VCLMULH_VX: InstructionOpcode = 0x1112;
+pub const OP_VCLZ_V: InstructionOpcode = 0x1113;
+pub const OP_VCPOP_V: InstructionOpcode = 0x1114;
+pub const OP_VCTZ_V: InstructionOpcode = 0x1115;
+pub const OP_VGHSH_VV: InstructionOpcode = 0x1116;
+pub const OP_VGMUL_VV: InstructionOpcode = 0x1117;
+pub const OP_VREV8_V: InstructionOpcode = 0x1118;
+pub const OP_VROL_VV: InstructionOpcode = 0x1119;
+pub const OP_VROL_VX: InstructionOpcode = 0x111a;
+pub const OP_VROR_VI: InstructionOpcode = 0x111b;
+pub const OP_VROR_VV: InstructionOpcode = 0x111c;
+pub const OP_VROR_VX: InstructionOpcode = 0x111d;
+pub const OP_VSHA2CH_VV: InstructionOpcode = 0x111e;
+pub const OP_VSHA2CL_VV: InstructionOpcode = 0x111f;
+pub const OP_VSHA2MS_VV: InstructionOpcode = 0x1120;
+pub const OP_VWSLL_VI: InstructionOpcode = 0x1121;
+pub const OP_VWSLL_VV: InstructionOpcode = 0x1122;
+pub const OP_VWSLL_VX: InstructionOpcode = 0x1123;
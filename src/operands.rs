@@ -0,0 +1,217 @@
+// Registers an instruction reads and writes, for dataflow tooling. Which
+// fields are registers comes from the templates: the reads= and writes=
+// columns of an entry, or the defaults of its format in build/codegen.rs.
+use super::v::{register_group_ranges, VType};
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterFile {
+    X,
+    F,
+    V,
+}
+
+// Where an operand takes its register number from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperandField {
+    // 5 bits from the least bit given, e.g. 7 for rd
+    Register(u8),
+    // 3 bits from the least bit given naming x8 - x15, as in compressed
+    // instructions
+    Compact(u8),
+    // Implied by the instruction, e.g. sp for c.lwsp
+    Fixed(u8),
+    // v0 when vm [25] is clear
+    Mask,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Operand {
+    pub file: RegisterFile,
+    pub field: OperandField,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    X(usize),
+    F(usize),
+    V(usize),
+}
+
+// One bit per register of each file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RegisterSet {
+    pub x: u32,
+    pub f: u32,
+    pub v: u32,
+}
+
+impl RegisterSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, register: Register) {
+        match register {
+            Register::X(index) => self.x |= 1 << index,
+            Register::F(index) => self.f |= 1 << index,
+            Register::V(index) => self.v |= 1 << index,
+        }
+    }
+
+    pub fn contains(&self, register: Register) -> bool {
+        match register {
+            Register::X(index) => self.x >> index & 1 != 0,
+            Register::F(index) => self.f >> index & 1 != 0,
+            Register::V(index) => self.v >> index & 1 != 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x | self.f | self.v == 0
+    }
+
+    // x registers first, then f and v, each in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = Register> + '_ {
+        let bits = |set: u32| (0..32).filter(move |index| set >> index & 1 != 0);
+        bits(self.x)
+            .map(Register::X)
+            .chain(bits(self.f).map(Register::F))
+            .chain(bits(self.v).map(Register::V))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DefUse {
+    pub reads: RegisterSet,
+    pub writes: RegisterSet,
+}
+
+// The registers of a word matching meta, e.g. from elf::decode_with_meta.
+// x0 is left out, it reads as 0 and drops writes. Vector operands cover their
+// whole register group under vtype, segment accesses all of their fields;
+// elements kept by the undisturbed policies do not count as reads. None if
+// the word uses vector registers and vtype is vill or makes its groups
+// reserved.
+pub fn def_use(instruction_bits: u32, meta: &InstructionMeta, vtype: VType) -> Option<DefUse> {
+    let vector = meta
+        .reads
+        .iter()
+        .chain(meta.writes)
+        .any(|operand| operand.file == RegisterFile::V);
+    let groups = if vector {
+        register_group_ranges(instruction_bits, meta.opcode, vtype.sew()?, vtype.lmul()?).ok()?
+    } else {
+        [None, None, None]
+    };
+    let registers = |operand: &Operand| -> Vec<Register> {
+        let register = match operand.field {
+            OperandField::Register(lower) => x(instruction_bits, lower as usize, 5, 0) as usize,
+            OperandField::Compact(lower) => x(instruction_bits, lower as usize, 3, 0) as usize + 8,
+            OperandField::Fixed(register) => register as usize,
+            OperandField::Mask if instruction_bits & 0x2000000 == 0 => 0,
+            OperandField::Mask => return vec![],
+        };
+        // fields the layout does not size hold a single register
+        let group = match operand.field {
+            OperandField::Register(7) => groups[0].clone(),
+            OperandField::Register(15) => groups[1].clone(),
+            OperandField::Register(20) => groups[2].clone(),
+            _ => None,
+        };
+        match operand.file {
+            RegisterFile::X if register == 0 => vec![],
+            RegisterFile::X => vec![Register::X(register)],
+            RegisterFile::F => vec![Register::F(register)],
+            RegisterFile::V => group
+                .unwrap_or(register..register + 1)
+                .map(Register::V)
+                .collect(),
+        }
+    };
+    let mut def_use = DefUse::default();
+    for operand in meta.reads {
+        for register in registers(operand) {
+            def_use.reads.insert(register);
+        }
+    }
+    for operand in meta.writes {
+        for register in registers(operand) {
+            def_use.writes.insert(register);
+        }
+    }
+    Some(def_use)
+}
+
+#[cfg(all(
+    test,
+    feature = "elf",
+    feature = "rvi",
+    feature = "rvc",
+    feature = "rvv"
+))]
+mod tests {
+    use super::*;
+    use crate::elf::decode_with_meta;
+
+    fn check(instruction_bits: u32, vtype: u64, reads: &[Register], writes: &[Register]) {
+        let config = FactoryConfig::new::<u64>(1).with_vector_profile(VectorProfile::V);
+        let (_, meta) = decode_with_meta(instruction_bits, &config).unwrap();
+        let def_use = def_use(instruction_bits, meta, VType::new(vtype)).unwrap();
+        let reads_found: Vec<Register> = def_use.reads.iter().collect();
+        let writes_found: Vec<Register> = def_use.writes.iter().collect();
+        assert_eq!(reads_found, reads, "reads of {:x}", instruction_bits);
+        assert_eq!(writes_found, writes, "writes of {:x}", instruction_bits);
+    }
+
+    #[test]
+    fn test_def_use() {
+        use Register::*;
+        // e32, m1 and e32, m4
+        let (m1, m4) = (0x10, 0x12);
+        // add a0, a1, a2 and sw a1, 0(a0)
+        check(0x00c58533, m1, &[X(11), X(12)], &[X(10)]);
+        check(0x00b52023, m1, &[X(10), X(11)], &[]);
+        // ecall takes the syscall number in a7, fence has no operands
+        check(
+            0x00000073,
+            m1,
+            &[X(10), X(11), X(12), X(13), X(14), X(15), X(17)],
+            &[X(10)],
+        );
+        check(0x0ff0000f, m1, &[], &[]);
+        // c.addi4spn a0, sp, 16, c.lwsp a0, 4(sp) and c.addi16sp sp, 32
+        check(0x0808, m1, &[X(2)], &[X(10)]);
+        check(0x4512, m1, &[X(2)], &[X(10)]);
+        check(0x6105, m1, &[X(2)], &[X(2)]);
+        // c.jalr a1, c.jr ra and c.mv zero, a0
+        check(0x9582, m1, &[X(11)], &[X(1)]);
+        check(0x8082, m1, &[X(1)], &[]);
+        check(0x802a, m1, &[X(10)], &[]);
+        // c.sub s0, s1 and c.beqz a0, 0
+        check(0x8c05, m1, &[X(8), X(9)], &[X(8)]);
+        check(0xc101, m1, &[X(10)], &[]);
+
+        let group = |start: usize, end: usize| -> Vec<Register> { (start..end).map(V).collect() };
+        // vadd.vv v4, v8, v12 under m1 and m4, masked under m4
+        check(0x02860257, m1, &[V(8), V(12)], &[V(4)]);
+        check(0x02860257, m4, &group(8, 16), &group(4, 8));
+        let masked = [&[V(0)][..], &group(8, 16)].concat();
+        check(0x00860257, m4, &masked, &group(4, 8));
+        // vadd.vv v1, v8, v12 is misaligned under m4
+        let config = FactoryConfig::new::<u64>(1).with_vector_profile(VectorProfile::V);
+        let (_, meta) = decode_with_meta(0x028600d7, &config).unwrap();
+        assert_eq!(def_use(0x028600d7, meta, VType::new(m4)), None);
+        assert_eq!(def_use(0x028600d7, meta, VType::ILLEGAL), None);
+        // vwadd.vv v8, v4, v6 under e32, m2 writes a group of 4
+        check(0xc6432457, 0x11, &group(4, 8), &group(8, 12));
+        // vmacc.vv v4, v8, v12 reads vd
+        check(0xb6c42257, m1, &[V(4), V(8), V(12)], &[V(4)]);
+        // vmv.x.s a0, v8 and vfmv.f.s fa0, v8
+        check(0x42802557, m4, &[V(8)], &[X(10)]);
+        check(0x42801557, m4, &[V(8)], &[F(10)]);
+        // vlseg2e32.v v8, (a0) under e32, m2, vsetvli a0, a1, e32, m1
+        check(0x22056407, 0x11, &[X(10)], &group(8, 12));
+        check(0x0105f557, m1, &[X(11)], &[X(10)]);
+    }
+}
@@ -0,0 +1,286 @@
+// Perfect hash generator. It only depends on core/std so build.rs can pull
+// it in with `#[path = "src/perfect_hash.rs"] mod perfect_hash;` and hand the
+// tables it finds to build/codegen.rs.
+//
+// Two hashes, each written out by build/codegen.rs:
+//   Modulo: hash_f(key) = ((key & 0xffff) + 1) * s0 + ((key >> 16 & 0xffff) + 1) * s1
+//           index(key)  = (G[hash_f1(key) % NG] + G[hash_f2(key) % NG]) % NG
+//   Pow2:   the same sum in 32 bits with odd salts, keeping its top log2(NG)
+//           bits, and `& (NG - 1)` in place of `% NG`
+// and the tables are built with the random graph algorithm of Czech, Havas
+// and Majewski.
+
+// Trials per table size before the graph is grown by 5%
+const TRIALS: usize = 150;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reduction {
+    Modulo,
+    // NG is a power of two
+    Pow2,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PerfectHash {
+    pub g: Vec<u32>,
+    pub salts: [[u32; 2]; 2],
+    pub reduction: Reduction,
+}
+
+impl PerfectHash {
+    // The first modulo table found, keys must be distinct, returns None
+    // otherwise
+    pub fn generate(keys: &[u32], seed: u64) -> Option<Self> {
+        Self::search(keys, seed, Reduction::Modulo, 0)
+    }
+
+    // Search for a small G. Modulo tables grow from
+    // len + 1 until one works, then shrink while one of candidates salt
+    // pairs still works for the next smaller size. Pow2 tables take the
+    // smallest power of two above len for which one of candidates pairs
+    // works, at least TRIALS are tried per size.
+    pub fn search(
+        keys: &[u32],
+        seed: u64,
+        reduction: Reduction,
+        candidates: usize,
+    ) -> Option<Self> {
+        let mut sorted = keys.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+        if sorted.len() != keys.len() {
+            return None;
+        }
+        let mut rng = SplitMix64(seed);
+        if reduction == Reduction::Pow2 {
+            let mut ng = 2;
+            while ng <= keys.len() {
+                ng *= 2;
+            }
+            loop {
+                let found = try_size(keys, ng, candidates.max(TRIALS), reduction, &mut rng);
+                if found.is_some() {
+                    return found;
+                }
+                ng *= 2;
+            }
+        }
+
+        let mut ng = keys.len() + 1;
+        let mut found = loop {
+            if let Some(found) = try_size(keys, ng, TRIALS, reduction, &mut rng) {
+                break found;
+            }
+            ng = usize::max(ng + 1, ng * 105 / 100);
+        };
+        while ng > keys.len() + 1 {
+            match try_size(keys, ng - 1, candidates, reduction, &mut rng) {
+                Some(smaller) => found = smaller,
+                None => break,
+            }
+            ng -= 1;
+        }
+        Some(found)
+    }
+
+    pub fn ng(&self) -> usize {
+        self.g.len()
+    }
+
+    #[inline(always)]
+    pub fn index(&self, key: u32) -> usize {
+        let ng = self.g.len();
+        let g1 = self.g[self.vertex(key, 0)] as usize;
+        let g2 = self.g[self.vertex(key, 1)] as usize;
+        match self.reduction {
+            Reduction::Modulo => (g1 + g2) % ng,
+            Reduction::Pow2 => (g1 + g2) & (ng - 1),
+        }
+    }
+
+    // Right shift of the 32-bit pow2 hash, keeping log2(NG) bits
+    pub fn shift(&self) -> u32 {
+        32 - self.g.len().trailing_zeros()
+    }
+
+    #[inline(always)]
+    fn vertex(&self, key: u32, function: usize) -> usize {
+        vertex(key, self.salts[function], self.g.len(), self.reduction)
+    }
+}
+
+fn try_size(
+    keys: &[u32],
+    ng: usize,
+    trials: usize,
+    reduction: Reduction,
+    rng: &mut SplitMix64,
+) -> Option<PerfectHash> {
+    for _ in 0..trials {
+        let mut salt = || match reduction {
+            Reduction::Modulo => rng.salt(ng),
+            Reduction::Pow2 => rng.next() as u32 | 1,
+        };
+        let salts = [[salt(), salt()], [salt(), salt()]];
+        if let Some(g) = assign(keys, &salts, ng, reduction) {
+            return Some(PerfectHash {
+                g,
+                salts,
+                reduction,
+            });
+        }
+    }
+    None
+}
+
+#[inline(always)]
+fn vertex(key: u32, salt: [u32; 2], ng: usize, reduction: Reduction) -> usize {
+    match reduction {
+        Reduction::Modulo => hash_f(key, salt) % ng,
+        Reduction::Pow2 => {
+            let sum = ((key & 0xffff) + 1)
+                .wrapping_mul(salt[0])
+                .wrapping_add(((key >> 16 & 0xffff) + 1).wrapping_mul(salt[1]));
+            (sum >> (32 - ng.trailing_zeros())) as usize
+        }
+    }
+}
+
+#[inline(always)]
+pub fn hash_f(key: u32, salt: [u32; 2]) -> usize {
+    ((key as usize & 0xffff) + 1) * salt[0] as usize
+        + ((key as usize >> 16 & 0xffff) + 1) * salt[1] as usize
+}
+
+// Build the graph with one edge per key, each vertex then gets a value so
+// that the values at both ends of an edge add up to the key index. This only
+// works when the graph is acyclic.
+fn assign(
+    keys: &[u32],
+    salts: &[[u32; 2]; 2],
+    ng: usize,
+    reduction: Reduction,
+) -> Option<Vec<u32>> {
+    let mut parent: Vec<usize> = (0..ng).collect();
+    let mut adjacent: Vec<Vec<(usize, usize)>> = vec![Vec::new(); ng];
+    for (index, key) in keys.iter().enumerate() {
+        let a = vertex(*key, salts[0], ng, reduction);
+        let b = vertex(*key, salts[1], ng, reduction);
+        let (root_a, root_b) = (find(&mut parent, a), find(&mut parent, b));
+        if root_a == root_b {
+            return None;
+        }
+        parent[root_a] = root_b;
+        adjacent[a].push((b, index));
+        adjacent[b].push((a, index));
+    }
+
+    let mut g = vec![0u32; ng];
+    let mut visited = vec![false; ng];
+    let mut stack = Vec::new();
+    for root in 0..ng {
+        if visited[root] {
+            continue;
+        }
+        visited[root] = true;
+        stack.push(root);
+        while let Some(vertex) = stack.pop() {
+            for &(next, index) in &adjacent[vertex] {
+                if !visited[next] {
+                    visited[next] = true;
+                    g[next] = ((index + ng - g[vertex] as usize) % ng) as u32;
+                    stack.push(next);
+                }
+            }
+        }
+    }
+    Some(g)
+}
+
+fn find(parent: &mut [usize], mut vertex: usize) -> usize {
+    while parent[vertex] != vertex {
+        parent[vertex] = parent[parent[vertex]];
+        vertex = parent[vertex];
+    }
+    vertex
+}
+
+// Small self-contained generator so tables only depend on the seed
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in [1, ng - 1], like random.randint(1, N - 1)
+    fn salt(&mut self, ng: usize) -> u32 {
+        if ng < 2 {
+            return 1;
+        }
+        (1 + self.next() % (ng as u64 - 1)) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every key maps to its own index, so no two share one
+    fn check(keys: &[u32], hash: &PerfectHash) {
+        for (index, key) in keys.iter().enumerate() {
+            assert_eq!(hash.index(*key), index, "key {:x}", key);
+        }
+        assert!(hash.ng() > keys.len());
+        if hash.reduction == Reduction::Pow2 {
+            assert!(hash.ng().is_power_of_two());
+        }
+    }
+
+    #[test]
+    fn test_perfect_hash() {
+        let mut rng = SplitMix64(1);
+        for size in [1, 2, 3, 10, 100, 500] {
+            let mut keys: Vec<u32> = (0..size).map(|_| rng.next() as u32).collect();
+            keys.sort_unstable();
+            keys.dedup();
+            check(&keys, &PerfectHash::generate(&keys, 0).unwrap());
+            for reduction in [Reduction::Modulo, Reduction::Pow2] {
+                let hash = PerfectHash::search(&keys, 0, reduction, 20).unwrap();
+                check(&keys, &hash);
+                assert_eq!(PerfectHash::search(&keys, 0, reduction, 20), Some(hash));
+            }
+        }
+        assert_eq!(PerfectHash::generate(&[1, 2, 1], 0), None);
+    }
+
+    // The keys of the generated decoders, as build.rs hashes them
+    #[cfg(all(feature = "rvi", feature = "rvc", feature = "rvv"))]
+    #[test]
+    fn test_decoder_keys() {
+        use crate::{rvc_decoder, rvi_decoder, rvv_decoder, InstructionMeta};
+        let metas: [&[InstructionMeta]; 3] = [
+            &rvi_decoder::INSTRUCTION_META,
+            &rvc_decoder::INSTRUCTION_META,
+            &rvv_decoder::INSTRUCTION_META,
+        ];
+        for meta in metas {
+            let mut keys: Vec<u32> = Vec::new();
+            for entry in meta {
+                if !keys.contains(&entry.match_bits) {
+                    keys.push(entry.match_bits);
+                }
+            }
+            for reduction in [Reduction::Modulo, Reduction::Pow2] {
+                check(
+                    &keys,
+                    &PerfectHash::search(&keys, 0, reduction, 100).unwrap(),
+                );
+            }
+        }
+    }
+}
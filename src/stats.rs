@@ -0,0 +1,164 @@
+// Instruction mix of a program, counted over its code or over a trace of the
+// PCs it executed, for ckb-decode --stats and benches/bench_decode.rs.
+use super::elf::{decode_with_meta, executable_sections};
+use super::*;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+// First line of the mix files read by benches/bench_decode.rs, followed by a
+// `<word in hex> <count>` line per word
+pub const MIX_HEADER: &str = "# ckb-vm-decoder instruction mix";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Mix {
+    // Keyed by template entry name, compressed ones prefixed with C.
+    pub opcodes: BTreeMap<String, u64>,
+    pub extensions: BTreeMap<&'static str, u64>,
+    pub formats: BTreeMap<&'static str, u64>,
+    pub words: BTreeMap<u32, u64>,
+    // Words no decoder knows, or PCs outside of the code
+    pub unknown: u64,
+}
+
+impl Mix {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, instruction_bits: u32, config: &FactoryConfig) {
+        let (_, meta) = match decode_with_meta(instruction_bits, config) {
+            Some(decoded) => decoded,
+            None => {
+                self.unknown += 1;
+                return;
+            }
+        };
+        let name = if instruction_bits & 0x3 != 0x3 {
+            format!("C.{}", meta.name)
+        } else {
+            meta.name.to_string()
+        };
+        *self.opcodes.entry(name).or_insert(0) += 1;
+        *self.extensions.entry(meta.extension).or_insert(0) += 1;
+        *self.formats.entry(meta.format).or_insert(0) += 1;
+        *self.words.entry(instruction_bits).or_insert(0) += 1;
+    }
+
+    // Every word of the executable sections once, None if elf_bytes is not
+    // an RV64 RISC-V ELF file
+    pub fn from_elf(elf_bytes: &[u8], config: &FactoryConfig) -> Option<Self> {
+        let mut mix = Self::new();
+        for section in executable_sections(elf_bytes)? {
+            for word in section.words(config) {
+                mix.add(word.instruction_bits, config);
+            }
+        }
+        Some(mix)
+    }
+
+    // The word at each PC of trace, one hex PC per line (0x optional, # starts
+    // a comment). None if elf_bytes is not an RV64 RISC-V ELF file or a line
+    // is not a PC.
+    pub fn from_trace(elf_bytes: &[u8], trace: &str, config: &FactoryConfig) -> Option<Self> {
+        let sections = executable_sections(elf_bytes)?;
+        let mut mix = Self::new();
+        for line in trace.lines() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let pc = u64::from_str_radix(line.trim_start_matches("0x"), 16).ok()?;
+            match sections
+                .iter()
+                .find_map(|section| section.instruction_bits_at(pc))
+            {
+                Some(instruction_bits) => mix.add(instruction_bits, config),
+                None => mix.unknown += 1,
+            }
+        }
+        Some(mix)
+    }
+
+    pub fn total(&self) -> u64 {
+        self.words.values().sum::<u64>() + self.unknown
+    }
+
+    // Histograms of opcodes, extensions and formats, most frequent first
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+        let total = self.total().max(1) as f64;
+        let mut histogram = |title: &str, mut counts: Vec<(&str, u64)>| {
+            counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+            writeln!(report, "{}:", title).unwrap();
+            for (name, count) in counts {
+                let percent = count as f64 * 100.0 / total;
+                writeln!(report, "{:>12} {:>6.2}%  {}", count, percent, name).unwrap();
+            }
+            writeln!(report).unwrap();
+        };
+        let opcodes = self.opcodes.iter();
+        histogram(
+            "opcode",
+            opcodes
+                .map(|(name, count)| (name.as_str(), *count))
+                .collect(),
+        );
+        let extensions = self.extensions.iter();
+        histogram(
+            "extension",
+            extensions.map(|(name, count)| (*name, *count)).collect(),
+        );
+        let formats = self.formats.iter();
+        histogram(
+            "format",
+            formats.map(|(name, count)| (*name, *count)).collect(),
+        );
+        writeln!(report, "{} words, {} unknown", self.total(), self.unknown).unwrap();
+        report
+    }
+
+    // The words weighted by their count, see MIX_HEADER
+    pub fn to_bench_mix(&self) -> String {
+        let mut mix = format!("{}\n", MIX_HEADER);
+        for (instruction_bits, count) in &self.words {
+            writeln!(mix, "{:08x} {}", instruction_bits, count).unwrap();
+        }
+        mix
+    }
+}
+
+#[cfg(all(test, feature = "rvi", feature = "rvm", feature = "rvc"))]
+mod tests {
+    use super::*;
+
+    const HINTS: &[u8] = include_bytes!("../tests/elf/hints.o");
+
+    #[test]
+    fn test_mix() {
+        let config = FactoryConfig::new::<u64>(1);
+        let mix = Mix::from_elf(HINTS, &config).unwrap();
+        assert_eq!(mix.total(), 7);
+        assert_eq!(mix.unknown, 0);
+        assert_eq!(mix.opcodes["C.LI"], 2);
+        assert_eq!(mix.opcodes["MUL"], 1);
+        // c.mv zero, a0 is counted as the HINT it is
+        assert_eq!(mix.opcodes["C.MV_HINT"], 1);
+        assert!(!mix.opcodes.contains_key("C.MV"));
+        assert_eq!(mix.extensions["C"], 5);
+        assert_eq!(mix.formats["CR"], 3);
+
+        // _start runs twice, the last PC is past the code
+        let trace = "0x0\n2\n4\n8\ne\n10\n# again\n0\n2\n4\n8\ne\n10\n12\n";
+        let mix = Mix::from_trace(HINTS, trace, &config).unwrap();
+        assert_eq!(mix.total(), 13);
+        assert_eq!(mix.unknown, 1);
+        assert_eq!(mix.opcodes["C.LI"], 4);
+        assert!(!mix.opcodes.contains_key("C.EBREAK"));
+        assert!(Mix::from_trace(HINTS, "pc", &config).is_none());
+
+        let bench_mix = mix.to_bench_mix();
+        let mut lines = bench_mix.lines();
+        assert_eq!(lines.next(), Some(MIX_HEADER));
+        assert_eq!(lines.next(), Some("00004505 2"));
+    }
+}
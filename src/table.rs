@@ -0,0 +1,192 @@
+// Decoder tables built at runtime, for instruction sets registered by users
+// rather than generated from templates/. Lookup is the same as in the
+// generated *_decoder.rs factories.
+use super::perfect_hash::PerfectHash;
+use super::*;
+use ckb_vm::instructions::{set_instruction_length_2, set_instruction_length_4};
+
+impl PerfectHash {
+    pub fn from_instructions(instructions: &[InstructionInfo], seed: u64) -> Option<Self> {
+        let keys: Vec<u32> = instructions.iter().map(|x| x.match_bits).collect();
+        PerfectHash::generate(&keys, seed)
+    }
+}
+
+pub struct InstructionTable {
+    instructions: Vec<InstructionInfo>,
+    // (mask, min_version) of the entries merged into each instruction
+    versions: Vec<Vec<(u32, u32)>>,
+    masks: Vec<u32>,
+    hash: PerfectHash,
+    set_instruction_length: fn(Instruction) -> Instruction,
+}
+
+impl InstructionTable {
+    // instruction_length is 2 for compressed sets and 4 otherwise. Entries
+    // sharing match bits are merged as build/codegen.rs does: they must use the
+    // same builder, and the first one is kept with the common mask. The most
+    // specific of them matching a word decides the version it needs.
+    pub fn new(
+        instructions: Vec<InstructionInfo>,
+        instruction_length: usize,
+        seed: u64,
+    ) -> Option<Self> {
+        let set_instruction_length = match instruction_length {
+            2 => set_instruction_length_2,
+            4 => set_instruction_length_4,
+            _ => return None,
+        };
+        let mut merged: Vec<InstructionInfo> = Vec::with_capacity(instructions.len());
+        let mut versions: Vec<Vec<(u32, u32)>> = Vec::with_capacity(instructions.len());
+        for inst in instructions {
+            let inst_versions = if inst.versions.is_empty() {
+                vec![(inst.mask, inst.min_version)]
+            } else {
+                inst.versions.to_vec()
+            };
+            match merged.iter().position(|x| x.match_bits == inst.match_bits) {
+                Some(idx) => {
+                    let first = &mut merged[idx];
+                    if first.builder as usize != inst.builder as usize {
+                        return None;
+                    }
+                    first.mask &= inst.mask;
+                    if first.xlen != inst.xlen {
+                        first.xlen = Xlen::Both;
+                    }
+                    first.min_version = u32::min(first.min_version, inst.min_version);
+                    versions[idx].extend(inst_versions);
+                }
+                None => {
+                    merged.push(inst);
+                    versions.push(inst_versions);
+                }
+            }
+        }
+        let hash = PerfectHash::from_instructions(&merged, seed)?;
+
+        // masks with the most entries are tried first
+        let mut by_masks: Vec<(u32, usize)> = Vec::new();
+        for inst in &merged {
+            match by_masks.iter_mut().find(|(mask, _)| *mask == inst.mask) {
+                Some((_, count)) => *count += 1,
+                None => by_masks.push((inst.mask, 1)),
+            }
+        }
+        by_masks.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        Some(InstructionTable {
+            instructions: merged,
+            versions,
+            masks: by_masks.into_iter().map(|(mask, _)| mask).collect(),
+            hash,
+            set_instruction_length,
+        })
+    }
+
+    pub fn hash(&self) -> &PerfectHash {
+        &self.hash
+    }
+
+    pub fn factory_with_config(
+        &self,
+        instruction_bits: u32,
+        config: &FactoryConfig,
+    ) -> Option<Instruction> {
+        for mask in &self.masks {
+            let idx = self.hash.index(instruction_bits & mask);
+            if let Some(info) = self.instructions.get(idx) {
+                if (info.mask & instruction_bits) == info.match_bits {
+                    let min_version = merged_min_version(
+                        &self.versions[idx],
+                        info.match_bits,
+                        instruction_bits,
+                        info.min_version,
+                    );
+                    if !info.xlen.supports(config) || config.version < min_version {
+                        return None;
+                    }
+                    let instruction = (info.builder)(instruction_bits, info.opcode, config)?;
+                    return Some((self.set_instruction_length)(instruction));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(all(test, feature = "rvi"))]
+mod tests {
+    use super::*;
+    use ckb_vm::instructions::{extract_opcode, set_instruction_length_4};
+
+    // Decodes like the generated rvi decoder, which hashes the same entries
+    #[test]
+    fn test_instruction_table() {
+        let table = InstructionTable::new(Vec::from(rvi_decoder::INSTRUCTION_LIST), 4, 0).unwrap();
+        let config = FactoryConfig::new::<u64>(1);
+        let mut word: u32 = 1;
+        let words = rvi_decoder::INSTRUCTION_LIST
+            .iter()
+            .map(|info| info.match_bits)
+            .chain((0..100_000).map(|_| {
+                // xorshift32
+                word ^= word << 13;
+                word ^= word >> 17;
+                word ^= word << 5;
+                word | 0x3
+            }));
+        for instruction_bits in words {
+            assert_eq!(
+                table.factory_with_config(instruction_bits, &config),
+                rvi_decoder::factory_with_config(instruction_bits, &config),
+                "{:x}",
+                instruction_bits
+            );
+        }
+        assert!(InstructionTable::new(Vec::from(rvi_decoder::INSTRUCTION_LIST), 3, 0).is_none());
+    }
+
+    fn first(_: u32, opcode: insts::InstructionOpcode, _: &FactoryConfig) -> Option<Instruction> {
+        Some(opcode as Instruction)
+    }
+
+    fn second(_: u32, _: insts::InstructionOpcode, _: &FactoryConfig) -> Option<Instruction> {
+        None
+    }
+
+    #[test]
+    fn test_merged_entries() {
+        let config = FactoryConfig::new::<u64>(0);
+        // the same match bits under two masks, merged as they share a builder
+        let entries = || {
+            vec![
+                InstructionInfo::new(0x707f, 0x33, insts::OP_ADD, first),
+                InstructionInfo::new(0xfe00707f, 0x33, insts::OP_SUB, first)
+                    .with_xlen(Xlen::Rv32)
+                    .with_min_version(1),
+            ]
+        };
+        let table = InstructionTable::new(entries(), 4, 0).unwrap();
+        // the first entry is kept with the common mask, available wherever
+        // one of them is
+        let instruction = table.factory_with_config(0x40000033, &config).unwrap();
+        assert_eq!(
+            instruction,
+            set_instruction_length_4(insts::OP_ADD as Instruction)
+        );
+        assert_eq!(extract_opcode(instruction), insts::OP_ADD);
+        assert_eq!(table.hash().index(0x33), 0);
+        // but words of the second entry still need its version
+        assert_eq!(table.factory_with_config(0x33, &config), None);
+        let instruction = table.factory_with_config(0x33, &FactoryConfig::new::<u64>(1));
+        assert_eq!(
+            instruction,
+            Some(set_instruction_length_4(insts::OP_ADD as Instruction))
+        );
+
+        let mut entries = entries();
+        // and rejected once they do not
+        entries[1] = InstructionInfo::new(0xfe00707f, 0x33, insts::OP_SUB, second);
+        assert!(InstructionTable::new(entries, 4, 0).is_none());
+    }
+}
@@ -1,11 +1,259 @@
+use super::opcodes as insts;
 use super::*;
-use ckb_vm::instructions::insts;
-use ckb_vm::instructions::{Instruction, Itype, Rtype, VItype, VVtype, VXtype};
+use ckb_vm::instructions::{extract_opcode, Instruction, Itype, Rtype, VItype, VVtype, VXtype};
+use std::ops::Range;
 
 fn vm(instruction_bits: u32) -> bool {
     instruction_bits & 0x2000000 != 0
 }
 
+fn encode_vm(vm: bool) -> u32 {
+    x(vm as u32, 0, 1, 25)
+}
+
+// Vector extension profiles, the embedded Zve* ones limit ELEN and whether
+// float operations exist at all. Zve64f and Zve64d decode the same, only
+// SEW, known at run time, tells whether a float operation needs D.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VectorProfile {
+    Zve32x,
+    Zve32f,
+    Zve64x,
+    Zve64f,
+    Zve64d,
+    V,
+}
+
+impl VectorProfile {
+    pub const fn elen(self) -> u32 {
+        match self {
+            VectorProfile::Zve32x | VectorProfile::Zve32f => 32,
+            _ => 64,
+        }
+    }
+
+    pub const fn has_f(self) -> bool {
+        !matches!(self, VectorProfile::Zve32x | VectorProfile::Zve64x)
+    }
+}
+
+// Without a profile every element width in the tables is accepted
+fn elen(config: &FactoryConfig) -> u32 {
+    match config.vector_profile {
+        Some(profile) => profile.elen(),
+        None => u32::MAX,
+    }
+}
+
+// Float operations need a profile with F, their opcodes are decode-only,
+// see src/opcodes.rs
+fn has_f(config: &FactoryConfig) -> bool {
+    match config.vector_profile {
+        Some(profile) => profile.has_f(),
+        None => false,
+    }
+}
+
+// Addressing mode of a vector load/store, taken from the mop field [27:26]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VMemAddressing {
+    UnitStride,
+    IndexedUnordered,
+    Strided,
+    IndexedOrdered,
+}
+
+// Vector loads/stores carry more fields than VXtype can hold, so the
+// immediate of an Itype is used to keep them:
+// [4:0]   => rs2 (lumop/sumop for unit-stride, stride register, or index vector)
+// [5]     => vm
+// [7:6]   => mop
+// [8]     => mew
+// [11:9]  => nf
+// [14:12] => width
+// rd holds vd for loads and vs3 for stores.
+#[derive(Debug, Clone, Copy)]
+pub struct VMemType(pub Instruction);
+
+impl VMemType {
+    pub fn new(opcode: insts::InstructionOpcode, instruction_bits: u32) -> Self {
+        VMemType(
+            Itype::new_u(
+                opcode,
+                rd(instruction_bits),
+                rs1(instruction_bits),
+                x(instruction_bits, 20, 12, 0) | x(instruction_bits, 12, 3, 12),
+            )
+            .0,
+        )
+    }
+
+    fn field(self, lower: usize, length: usize) -> u32 {
+        x(Itype(self.0).immediate_u(), lower, length, 0)
+    }
+
+    pub fn vd(self) -> usize {
+        Itype(self.0).rd()
+    }
+
+    pub fn rs1(self) -> usize {
+        Itype(self.0).rs1()
+    }
+
+    pub fn rs2(self) -> usize {
+        self.field(0, 5) as usize
+    }
+
+    pub fn vm(self) -> bool {
+        self.field(5, 1) != 0
+    }
+
+    pub fn mop(self) -> u8 {
+        self.field(6, 2) as u8
+    }
+
+    pub fn addressing(self) -> VMemAddressing {
+        match self.mop() {
+            0 => VMemAddressing::UnitStride,
+            1 => VMemAddressing::IndexedUnordered,
+            2 => VMemAddressing::Strided,
+            _ => VMemAddressing::IndexedOrdered,
+        }
+    }
+
+    // lumop for loads and sumop for stores, only meaningful for unit-stride
+    pub fn lumop(self) -> u8 {
+        self.rs2() as u8
+    }
+
+    pub fn sumop(self) -> u8 {
+        self.rs2() as u8
+    }
+
+    // Raw nf field, the number of segments (or registers for whole register
+    // accesses) is nf + 1
+    pub fn nf(self) -> u8 {
+        self.field(9, 3) as u8
+    }
+
+    pub fn nfields(self) -> u8 {
+        self.nf() + 1
+    }
+
+    pub fn width(self) -> u8 {
+        self.field(12, 3) as u8
+    }
+
+    // Element width in bits encoded by mew and width. For indexed accesses this
+    // is the EEW of the index vector, the data EEW is SEW. None for the
+    // widths of scalar floating point accesses, e.g. FLW.
+    pub fn eew(self) -> Option<u32> {
+        eew(self.field(8, 1), self.width() as u32)
+    }
+}
+
+// [28]    => mew
+// [14:12] => width
+fn eew(mew: u32, width: u32) -> Option<u32> {
+    let eew = match width {
+        0b000 => 8,
+        0b101 => 16,
+        0b110 => 32,
+        0b111 => 64,
+        _ => return None,
+    };
+    Some(eew << (mew * 4))
+}
+
+fn vmem_builder(
+    instruction_bits: u32,
+    opcode: insts::InstructionOpcode,
+    config: &FactoryConfig,
+) -> Option<Instruction> {
+    if eew(x(instruction_bits, 28, 1, 0), funct3(instruction_bits))? > elen(config) {
+        return None;
+    }
+    let inst = VMemType::new(opcode, instruction_bits);
+    // A segment access needs nf+1 register groups starting at vd, whatever
+    // LMUL is, running past v31 is reserved
+    if inst.vd() + inst.nfields() as usize > 32 {
+        return None;
+    }
+    Some(inst.0)
+}
+
+pub fn vunit_stride_builder(
+    instruction_bits: u32,
+    opcode: insts::InstructionOpcode,
+    config: &FactoryConfig,
+) -> Option<Instruction> {
+    // lumop/sumop: 0b00000 unit-stride, 0b01011 mask (nf must be 0),
+    // 0b10000 fault-only-first (loads only), the rest are reserved
+    let is_load = instruction_bits & 0x7f == 0x07;
+    match (x(instruction_bits, 20, 5, 0), x(instruction_bits, 29, 3, 0)) {
+        (0b00000, _) | (0b01011, 0) => vmem_builder(instruction_bits, opcode, config),
+        (0b10000, _) if is_load => vmem_builder(instruction_bits, opcode, config),
+        _ => None,
+    }
+}
+
+pub fn vstrided_builder(
+    instruction_bits: u32,
+    opcode: insts::InstructionOpcode,
+    config: &FactoryConfig,
+) -> Option<Instruction> {
+    vmem_builder(instruction_bits, opcode, config)
+}
+
+pub fn vindexed_builder(
+    instruction_bits: u32,
+    opcode: insts::InstructionOpcode,
+    config: &FactoryConfig,
+) -> Option<Instruction> {
+    vmem_builder(instruction_bits, opcode, config)
+}
+
+pub fn vwhole_register_builder(
+    instruction_bits: u32,
+    _: insts::InstructionOpcode,
+    config: &FactoryConfig,
+) -> Option<Instruction> {
+    // VL<nf>RE<eew>_V and VS<nf>R_V, nf and the width pick the opcode.
+    // Only 1, 2, 4 or 8 registers can be accessed, templates/rvv has no
+    // entries for the other nf values.
+    let opcode = match (
+        instruction_bits & 0x7f,
+        x(instruction_bits, 29, 3, 0),
+        funct3(instruction_bits),
+    ) {
+        (0x07, 0, 0b000) => insts::OP_VL1RE8_V,
+        (0x07, 0, 0b101) => insts::OP_VL1RE16_V,
+        (0x07, 0, 0b110) => insts::OP_VL1RE32_V,
+        (0x07, 0, 0b111) => insts::OP_VL1RE64_V,
+        (0x07, 1, 0b000) => insts::OP_VL2RE8_V,
+        (0x07, 1, 0b101) => insts::OP_VL2RE16_V,
+        (0x07, 1, 0b110) => insts::OP_VL2RE32_V,
+        (0x07, 1, 0b111) => insts::OP_VL2RE64_V,
+        (0x07, 3, 0b000) => insts::OP_VL4RE8_V,
+        (0x07, 3, 0b101) => insts::OP_VL4RE16_V,
+        (0x07, 3, 0b110) => insts::OP_VL4RE32_V,
+        (0x07, 3, 0b111) => insts::OP_VL4RE64_V,
+        (0x07, 7, 0b000) => insts::OP_VL8RE8_V,
+        (0x07, 7, 0b101) => insts::OP_VL8RE16_V,
+        (0x07, 7, 0b110) => insts::OP_VL8RE32_V,
+        (0x07, 7, 0b111) => insts::OP_VL8RE64_V,
+        (0x27, 0, 0b000) => insts::OP_VS1R_V,
+        (0x27, 1, 0b000) => insts::OP_VS2R_V,
+        (0x27, 3, 0b000) => insts::OP_VS4R_V,
+        (0x27, 7, 0b000) => insts::OP_VS8R_V,
+        _ => return None,
+    };
+    if eew(0, funct3(instruction_bits))? > elen(config) {
+        return None;
+    }
+    Some(VMemType::new(opcode, instruction_bits).0)
+}
+
 pub fn vx_builder(
     instruction_bits: u32,
     opcode: insts::InstructionOpcode,
@@ -40,6 +288,93 @@ pub fn vv_builder(
     )
 }
 
+// OPFVV, kept apart from vv_builder so float operations can be told apart
+pub fn fvv_builder(
+    instruction_bits: u32,
+    opcode: insts::InstructionOpcode,
+    config: &FactoryConfig,
+) -> Option<Instruction> {
+    if !has_f(config) {
+        return None;
+    }
+    Some(
+        VVtype::new(
+            opcode,
+            rd(instruction_bits),
+            rs1(instruction_bits),
+            rs2(instruction_bits),
+            vm(instruction_bits),
+        )
+        .0,
+    )
+}
+
+// VFMV_F_S writes element 0 of vs2 to the f register rd, so it is an
+// Rtype with rd naming f[rd] rather than a vector register
+pub fn vfmv_f_s_builder(
+    instruction_bits: u32,
+    opcode: insts::InstructionOpcode,
+    config: &FactoryConfig,
+) -> Option<Instruction> {
+    if !has_f(config) {
+        return None;
+    }
+    Some(Rtype::new(opcode, rd(instruction_bits), 0, rs2(instruction_bits)).0)
+}
+
+// Vector-scalar float operations (OPFVF) have the same layout as VXtype,
+// except that the scalar operand is an f register.
+#[derive(Debug, Clone, Copy)]
+pub struct VFtype(pub Instruction);
+
+impl VFtype {
+    pub fn new(
+        opcode: insts::InstructionOpcode,
+        vd: usize,
+        fs1: usize,
+        vs2: usize,
+        vm: bool,
+    ) -> Self {
+        VFtype(VXtype::new(opcode, vd, fs1, vs2, vm).0)
+    }
+
+    pub fn vd(self) -> usize {
+        VXtype(self.0).vd()
+    }
+
+    pub fn fs1(self) -> usize {
+        VXtype(self.0).rs1()
+    }
+
+    pub fn vs2(self) -> usize {
+        VXtype(self.0).vs2()
+    }
+
+    pub fn vm(self) -> bool {
+        VXtype(self.0).vm()
+    }
+}
+
+pub fn vf_builder(
+    instruction_bits: u32,
+    opcode: insts::InstructionOpcode,
+    config: &FactoryConfig,
+) -> Option<Instruction> {
+    if !has_f(config) {
+        return None;
+    }
+    Some(
+        VFtype::new(
+            opcode,
+            rd(instruction_bits),
+            rs1(instruction_bits),
+            rs2(instruction_bits),
+            vm(instruction_bits),
+        )
+        .0,
+    )
+}
+
 pub fn vi_builder(
     instruction_bits: u32,
     opcode: insts::InstructionOpcode,
@@ -57,11 +392,225 @@ pub fn vi_builder(
     )
 }
 
+// Vector crypto, only decoded when enabled in the config. The element-group
+// forms (.vv/.vs of Zvkg, Zvkned and Zvknh) keep the VVtype operand layout,
+// the 5-bit uimm of vaeskf1/vaeskf2/vwsll.vi sits in the vs1 field.
+pub fn vkvv_builder(
+    instruction_bits: u32,
+    opcode: insts::InstructionOpcode,
+    config: &FactoryConfig,
+) -> Option<Instruction> {
+    if !config.vector_crypto {
+        return None;
+    }
+    vv_builder(instruction_bits, opcode, config)
+}
+
+pub fn vkvx_builder(
+    instruction_bits: u32,
+    opcode: insts::InstructionOpcode,
+    config: &FactoryConfig,
+) -> Option<Instruction> {
+    if !config.vector_crypto {
+        return None;
+    }
+    vx_builder(instruction_bits, opcode, config)
+}
+
+pub fn vkvi_builder(
+    instruction_bits: u32,
+    opcode: insts::InstructionOpcode,
+    config: &FactoryConfig,
+) -> Option<Instruction> {
+    if !config.vector_crypto {
+        return None;
+    }
+    vi_builder(instruction_bits, opcode, config)
+}
+
+// vror.vi takes a 6-bit rotate amount, uimm[5] lives in bit 26
+pub fn vror_vi_builder(
+    instruction_bits: u32,
+    opcode: insts::InstructionOpcode,
+    config: &FactoryConfig,
+) -> Option<Instruction> {
+    if !config.vector_crypto {
+        return None;
+    }
+    Some(
+        VItype::new(
+            opcode,
+            rd(instruction_bits),
+            rs2(instruction_bits),
+            x(instruction_bits, 15, 5, 0) | x(instruction_bits, 26, 1, 5),
+            vm(instruction_bits),
+        )
+        .0,
+    )
+}
+
+// LMUL as encoded in vlmul, fractional values included
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lmul {
+    Mf8,
+    Mf4,
+    Mf2,
+    M1,
+    M2,
+    M4,
+    M8,
+}
+
+impl Lmul {
+    pub fn log2(self) -> i8 {
+        match self {
+            Lmul::Mf8 => -3,
+            Lmul::Mf4 => -2,
+            Lmul::Mf2 => -1,
+            Lmul::M1 => 0,
+            Lmul::M2 => 1,
+            Lmul::M4 => 2,
+            Lmul::M8 => 3,
+        }
+    }
+
+    pub fn from_log2(log2: i8) -> Option<Self> {
+        match log2 {
+            -3 => Some(Lmul::Mf8),
+            -2 => Some(Lmul::Mf4),
+            -1 => Some(Lmul::Mf2),
+            0 => Some(Lmul::M1),
+            1 => Some(Lmul::M2),
+            2 => Some(Lmul::M4),
+            3 => Some(Lmul::M8),
+            _ => None,
+        }
+    }
+
+    // Number of registers in a group, a fractional LMUL still occupies one
+    pub fn registers(self) -> usize {
+        1 << self.log2().max(0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VTypeError {
+    // vlmul=4 is reserved
+    ReservedLmul,
+    // vsew=4..7 are reserved
+    ReservedSew,
+    // Bits above vma must be zero, this also covers vill
+    ReservedBits,
+}
+
+// vtype layout:
+// [2:0] => vlmul
+// [5:3] => vsew
+// [6]   => vta
+// [7]   => vma
+// When vill is set, all the other fields are zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VType {
+    vlmul: u8,
+    vsew: u8,
+    vta: bool,
+    vma: bool,
+    vill: bool,
+}
+
+impl VType {
+    pub const ILLEGAL: VType = VType {
+        vlmul: 0,
+        vsew: 0,
+        vta: false,
+        vma: false,
+        vill: true,
+    };
+
+    pub fn decode(bits: u64) -> Result<Self, VTypeError> {
+        if bits >> 8 != 0 {
+            return Err(VTypeError::ReservedBits);
+        }
+        let vlmul = (bits & 0x7) as u8;
+        let vsew = (bits >> 3 & 0x7) as u8;
+        if vlmul == 4 {
+            return Err(VTypeError::ReservedLmul);
+        }
+        if vsew > 3 {
+            return Err(VTypeError::ReservedSew);
+        }
+        Ok(VType {
+            vlmul,
+            vsew,
+            vta: bits & 0x40 != 0,
+            vma: bits & 0x80 != 0,
+            vill: false,
+        })
+    }
+
+    // Same as decode, but a reserved setting yields vill like vset{i}vl{i} does
+    pub fn new(bits: u64) -> Self {
+        Self::decode(bits).unwrap_or(Self::ILLEGAL)
+    }
+
+    // The vtype immediate of a decoded VSETVLI/VSETIVLI, VSETVL takes
+    // vtype from rs2 so it has none.
+    pub fn from_instruction(instruction: Instruction) -> Option<Self> {
+        match extract_opcode(instruction) {
+            insts::OP_VSETVLI | insts::OP_VSETIVLI => {
+                Some(Self::new(u64::from(Itype(instruction).immediate_u())))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn vill(self) -> bool {
+        self.vill
+    }
+
+    // SEW and LMUL, None under vill where they are undefined
+    pub fn sew(self) -> Option<u32> {
+        (!self.vill).then(|| 8 << self.vsew)
+    }
+
+    pub fn lmul(self) -> Option<Lmul> {
+        if self.vill {
+            return None;
+        }
+        Lmul::from_log2(((self.vlmul as i8) << 5) >> 5)
+    }
+
+    pub fn ta(self) -> bool {
+        self.vta
+    }
+
+    pub fn ma(self) -> bool {
+        self.vma
+    }
+
+    // VLMAX = LMUL * VLEN / SEW, 0 under vill as vl is then 0 too
+    pub fn vlmax(self, vlen: u32) -> u32 {
+        let (sew, lmul) = match (self.sew(), self.lmul()) {
+            (Some(sew), Some(lmul)) => (sew, lmul.log2()),
+            _ => return 0,
+        };
+        if lmul >= 0 {
+            (vlen << lmul) / sew
+        } else {
+            (vlen >> -lmul) / sew
+        }
+    }
+}
+
 pub fn vsetvli_builder(
     instruction_bits: u32,
     _: insts::InstructionOpcode,
-    _: &FactoryConfig,
+    config: &FactoryConfig,
 ) -> Option<Instruction> {
+    let vtype = VType::new(u64::from(x(instruction_bits, 20, 11, 0)));
+    if matches!(vtype.sew(), Some(sew) if sew > elen(config)) {
+        return None;
+    }
     Some(
         Itype::new_u(
             insts::OP_VSETVLI,
@@ -76,8 +625,12 @@ pub fn vsetvli_builder(
 pub fn vsetivli_builder(
     instruction_bits: u32,
     _: insts::InstructionOpcode,
-    _: &FactoryConfig,
+    config: &FactoryConfig,
 ) -> Option<Instruction> {
+    let vtype = VType::new(u64::from(x(instruction_bits, 20, 10, 0)));
+    if matches!(vtype.sew(), Some(sew) if sew > elen(config)) {
+        return None;
+    }
     Some(
         Itype::new_u(
             insts::OP_VSETIVLI,
@@ -104,3 +657,960 @@ pub fn vsetvl_builder(
         .0,
     )
 }
+
+// Encoders, the inverses of the builders above. The fields of loads and
+// stores all come from the instruction, only the major opcode is taken from
+// the entry as whole register accesses share theirs.
+fn vmem_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let inst = VMemType(instruction);
+    let imm = Itype(instruction).immediate_u();
+    Some(
+        (match_bits & 0x7f)
+            | encode_rd(inst.vd())
+            | encode_rs1(inst.rs1())
+            | x(imm, 0, 12, 20)
+            | x(imm, 12, 3, 12),
+    )
+}
+
+pub fn vunit_stride_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    vmem_encoder(instruction, match_bits)
+}
+
+pub fn vstrided_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    vmem_encoder(instruction, match_bits)
+}
+
+pub fn vindexed_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    vmem_encoder(instruction, match_bits)
+}
+
+pub fn vwhole_register_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    vmem_encoder(instruction, match_bits)
+}
+
+pub fn vx_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = VXtype(instruction);
+    Some(
+        match_bits
+            | encode_rd(i.vd())
+            | encode_rs1(i.rs1())
+            | encode_rs2(i.vs2())
+            | encode_vm(i.vm()),
+    )
+}
+
+pub fn vv_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = VVtype(instruction);
+    Some(
+        match_bits
+            | encode_rd(i.vd())
+            | encode_rs1(i.vs1())
+            | encode_rs2(i.vs2())
+            | encode_vm(i.vm()),
+    )
+}
+
+pub fn fvv_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    vv_encoder(instruction, match_bits)
+}
+
+pub fn vfmv_f_s_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    r_encoder(instruction, match_bits)
+}
+
+pub fn vf_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = VFtype(instruction);
+    Some(
+        match_bits
+            | encode_rd(i.vd())
+            | encode_rs1(i.fs1())
+            | encode_rs2(i.vs2())
+            | encode_vm(i.vm()),
+    )
+}
+
+pub fn vi_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = VItype(instruction);
+    Some(
+        match_bits
+            | encode_rd(i.vd())
+            | x(i.immediate_u(), 0, 5, 15)
+            | encode_rs2(i.vs2())
+            | encode_vm(i.vm()),
+    )
+}
+
+pub fn vkvv_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    vv_encoder(instruction, match_bits)
+}
+
+pub fn vkvx_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    vx_encoder(instruction, match_bits)
+}
+
+pub fn vkvi_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    vi_encoder(instruction, match_bits)
+}
+
+pub fn vror_vi_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let imm = VItype(instruction).immediate_u();
+    Some(vi_encoder(instruction, match_bits)? | x(imm, 5, 1, 26))
+}
+
+pub fn vsetvli_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = Itype(instruction);
+    Some(match_bits | encode_rd(i.rd()) | encode_rs1(i.rs1()) | x(i.immediate_u(), 0, 11, 20))
+}
+
+pub fn vsetivli_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    let i = Itype(instruction);
+    Some(match_bits | encode_rd(i.rd()) | encode_rs1(i.rs1()) | x(i.immediate_u(), 0, 10, 20))
+}
+
+pub fn vsetvl_encoder(instruction: Instruction, match_bits: u32) -> Option<u32> {
+    r_encoder(instruction, match_bits)
+}
+
+// Reasons the spec reserves an encoding given the current SEW/LMUL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterGroupError {
+    // An operand's EMUL falls outside [1/8, 8]
+    IllegalEmul,
+    // A register group does not start at a multiple of its size
+    Misaligned,
+    // A masked instruction writes a register group containing v0
+    MaskOverlap,
+    // The destination overlaps a source in a way the spec does not allow
+    SourceOverlap,
+    // A segment access needs more than 8 registers, or runs past v31
+    SegmentTooLarge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VOperand {
+    // Not a vector register: a scalar, an immediate or an unused field
+    None,
+    // A register group whose EEW is SEW * 2^n, so EMUL is LMUL * 2^n
+    Group(i8),
+    // A single register holding a mask
+    Mask,
+    // Element 0 of a single register, e.g. a reduction scalar
+    Scalar,
+}
+
+struct VLayout {
+    vd: VOperand,
+    vs1: VOperand,
+    vs2: VOperand,
+    // vd is a source (stores), nothing is written
+    vd_is_source: bool,
+    // The destination may not overlap any source at all
+    no_overlap: bool,
+}
+
+impl VLayout {
+    fn new(vd: VOperand, vs1: VOperand, vs2: VOperand) -> Self {
+        VLayout {
+            vd,
+            vs1,
+            vs2,
+            vd_is_source: false,
+            no_overlap: false,
+        }
+    }
+}
+
+fn arithmetic_layout(instruction_bits: u32, opcode: insts::InstructionOpcode) -> Option<VLayout> {
+    use VOperand::*;
+    // OPIVV, OPFVV and OPMVV read vs1 as a vector
+    let vs1 = match funct3(instruction_bits) {
+        0b000..=0b010 => Group(0),
+        _ => None,
+    };
+    let layout = match opcode {
+        insts::OP_VSETVLI | insts::OP_VSETIVLI | insts::OP_VSETVL => return Option::None,
+        insts::OP_VWADD_VV
+        | insts::OP_VWADD_VX
+        | insts::OP_VWADDU_VV
+        | insts::OP_VWADDU_VX
+        | insts::OP_VWSUB_VV
+        | insts::OP_VWSUB_VX
+        | insts::OP_VWSUBU_VV
+        | insts::OP_VWSUBU_VX
+        | insts::OP_VWMUL_VV
+        | insts::OP_VWMUL_VX
+        | insts::OP_VWMULSU_VV
+        | insts::OP_VWMULSU_VX
+        | insts::OP_VWMULU_VV
+        | insts::OP_VWMULU_VX
+        | insts::OP_VWMACC_VV
+        | insts::OP_VWMACC_VX
+        | insts::OP_VWMACCSU_VV
+        | insts::OP_VWMACCSU_VX
+        | insts::OP_VWMACCU_VV
+        | insts::OP_VWMACCU_VX
+        | insts::OP_VWMACCUS_VX
+        | insts::OP_VFWADD_VV
+        | insts::OP_VFWADD_VF
+        | insts::OP_VFWSUB_VV
+        | insts::OP_VFWSUB_VF
+        | insts::OP_VFWMUL_VV
+        | insts::OP_VFWMUL_VF
+        | insts::OP_VFWMACC_VV
+        | insts::OP_VFWMACC_VF
+        | insts::OP_VFWNMACC_VV
+        | insts::OP_VFWNMACC_VF
+        | insts::OP_VFWMSAC_VV
+        | insts::OP_VFWMSAC_VF
+        | insts::OP_VFWNMSAC_VV
+        | insts::OP_VFWNMSAC_VF => VLayout::new(Group(1), vs1, Group(0)),
+        insts::OP_VWADD_WV
+        | insts::OP_VWADD_WX
+        | insts::OP_VWADDU_WV
+        | insts::OP_VWADDU_WX
+        | insts::OP_VWSUB_WV
+        | insts::OP_VWSUB_WX
+        | insts::OP_VWSUBU_WV
+        | insts::OP_VWSUBU_WX
+        | insts::OP_VFWADD_WV
+        | insts::OP_VFWADD_WF
+        | insts::OP_VFWSUB_WV
+        | insts::OP_VFWSUB_WF => VLayout::new(Group(1), vs1, Group(1)),
+        insts::OP_VFWCVT_F_F_V
+        | insts::OP_VFWCVT_F_X_V
+        | insts::OP_VFWCVT_F_XU_V
+        | insts::OP_VFWCVT_RTZ_X_F_V
+        | insts::OP_VFWCVT_RTZ_XU_F_V
+        | insts::OP_VFWCVT_X_F_V
+        | insts::OP_VFWCVT_XU_F_V => VLayout::new(Group(1), None, Group(0)),
+        insts::OP_VNCLIP_WI
+        | insts::OP_VNCLIP_WV
+        | insts::OP_VNCLIP_WX
+        | insts::OP_VNCLIPU_WI
+        | insts::OP_VNCLIPU_WV
+        | insts::OP_VNCLIPU_WX
+        | insts::OP_VNSRA_WI
+        | insts::OP_VNSRA_WV
+        | insts::OP_VNSRA_WX
+        | insts::OP_VNSRL_WI
+        | insts::OP_VNSRL_WV
+        | insts::OP_VNSRL_WX => VLayout::new(Group(0), vs1, Group(1)),
+        insts::OP_VFNCVT_F_F_W
+        | insts::OP_VFNCVT_F_X_W
+        | insts::OP_VFNCVT_F_XU_W
+        | insts::OP_VFNCVT_ROD_F_F_W
+        | insts::OP_VFNCVT_RTZ_X_F_W
+        | insts::OP_VFNCVT_RTZ_XU_F_W
+        | insts::OP_VFNCVT_X_F_W
+        | insts::OP_VFNCVT_XU_F_W => VLayout::new(Group(0), None, Group(1)),
+        insts::OP_VSEXT_VF2 | insts::OP_VZEXT_VF2 => VLayout::new(Group(0), None, Group(-1)),
+        insts::OP_VSEXT_VF4 | insts::OP_VZEXT_VF4 => VLayout::new(Group(0), None, Group(-2)),
+        insts::OP_VSEXT_VF8 | insts::OP_VZEXT_VF8 => VLayout::new(Group(0), None, Group(-3)),
+        insts::OP_VFCLASS_V
+        | insts::OP_VFCVT_F_X_V
+        | insts::OP_VFCVT_F_XU_V
+        | insts::OP_VFCVT_RTZ_X_F_V
+        | insts::OP_VFCVT_RTZ_XU_F_V
+        | insts::OP_VFCVT_X_F_V
+        | insts::OP_VFCVT_XU_F_V
+        | insts::OP_VFREC7_V
+        | insts::OP_VFRSQRT7_V
+        | insts::OP_VFSQRT_V => VLayout::new(Group(0), None, Group(0)),
+        insts::OP_VMADC_VI
+        | insts::OP_VMADC_VIM
+        | insts::OP_VMADC_VV
+        | insts::OP_VMADC_VVM
+        | insts::OP_VMADC_VX
+        | insts::OP_VMADC_VXM
+        | insts::OP_VMSBC_VV
+        | insts::OP_VMSBC_VVM
+        | insts::OP_VMSBC_VX
+        | insts::OP_VMSBC_VXM
+        | insts::OP_VMSEQ_VI
+        | insts::OP_VMSEQ_VV
+        | insts::OP_VMSEQ_VX
+        | insts::OP_VMSGT_VI
+        | insts::OP_VMSGT_VX
+        | insts::OP_VMSGTU_VI
+        | insts::OP_VMSGTU_VX
+        | insts::OP_VMSLE_VI
+        | insts::OP_VMSLE_VV
+        | insts::OP_VMSLE_VX
+        | insts::OP_VMSLEU_VI
+        | insts::OP_VMSLEU_VV
+        | insts::OP_VMSLEU_VX
+        | insts::OP_VMSLT_VV
+        | insts::OP_VMSLT_VX
+        | insts::OP_VMSLTU_VV
+        | insts::OP_VMSLTU_VX
+        | insts::OP_VMSNE_VI
+        | insts::OP_VMSNE_VV
+        | insts::OP_VMSNE_VX
+        | insts::OP_VMFEQ_VF
+        | insts::OP_VMFEQ_VV
+        | insts::OP_VMFGE_VF
+        | insts::OP_VMFGT_VF
+        | insts::OP_VMFLE_VF
+        | insts::OP_VMFLE_VV
+        | insts::OP_VMFLT_VF
+        | insts::OP_VMFLT_VV
+        | insts::OP_VMFNE_VF
+        | insts::OP_VMFNE_VV => VLayout::new(Mask, vs1, Group(0)),
+        insts::OP_VMAND_MM
+        | insts::OP_VMANDNOT_MM
+        | insts::OP_VMNAND_MM
+        | insts::OP_VMNOR_MM
+        | insts::OP_VMOR_MM
+        | insts::OP_VMORNOT_MM
+        | insts::OP_VMXNOR_MM
+        | insts::OP_VMXOR_MM => VLayout::new(Mask, Mask, Mask),
+        insts::OP_VCPOP_M | insts::OP_VFIRST_M => VLayout::new(None, None, Mask),
+        insts::OP_VMSBF_M | insts::OP_VMSIF_M | insts::OP_VMSOF_M => VLayout {
+            no_overlap: true,
+            ..VLayout::new(Mask, None, Mask)
+        },
+        insts::OP_VIOTA_M => VLayout {
+            no_overlap: true,
+            ..VLayout::new(Group(0), None, Mask)
+        },
+        insts::OP_VID_V => VLayout::new(Group(0), None, None),
+        insts::OP_VREDAND_VS
+        | insts::OP_VREDMAX_VS
+        | insts::OP_VREDMAXU_VS
+        | insts::OP_VREDMIN_VS
+        | insts::OP_VREDMINU_VS
+        | insts::OP_VREDOR_VS
+        | insts::OP_VREDSUM_VS
+        | insts::OP_VREDXOR_VS
+        | insts::OP_VWREDSUM_VS
+        | insts::OP_VWREDSUMU_VS
+        | insts::OP_VFREDMAX_VS
+        | insts::OP_VFREDMIN_VS
+        | insts::OP_VFREDOSUM_VS
+        | insts::OP_VFREDUSUM_VS
+        | insts::OP_VFWREDOSUM_VS
+        | insts::OP_VFWREDUSUM_VS => VLayout::new(Scalar, Scalar, Group(0)),
+        insts::OP_VMV_X_S | insts::OP_VFMV_F_S => VLayout::new(None, None, Scalar),
+        insts::OP_VMV_S_X | insts::OP_VFMV_S_F => VLayout::new(Scalar, None, None),
+        insts::OP_VMV_V_I | insts::OP_VMV_V_V | insts::OP_VMV_V_X | insts::OP_VFMV_V_F => {
+            VLayout::new(Group(0), vs1, None)
+        }
+        insts::OP_VSLIDEUP_VI
+        | insts::OP_VSLIDEUP_VX
+        | insts::OP_VSLIDE1UP_VX
+        | insts::OP_VFSLIDE1UP_VF
+        | insts::OP_VRGATHER_VI
+        | insts::OP_VRGATHER_VV
+        | insts::OP_VRGATHER_VX => VLayout {
+            no_overlap: true,
+            ..VLayout::new(Group(0), vs1, Group(0))
+        },
+        insts::OP_VCOMPRESS_VM => VLayout {
+            no_overlap: true,
+            ..VLayout::new(Group(0), Mask, Group(0))
+        },
+        _ => VLayout::new(Group(0), vs1, Group(0)),
+    };
+    Some(layout)
+}
+
+fn log2(value: u32) -> i8 {
+    value.trailing_zeros() as i8
+}
+
+// Register range [start, end) of an operand, checking EMUL and alignment
+fn register_group(
+    register: usize,
+    operand: VOperand,
+    lmul: i8,
+) -> Result<Option<(usize, usize)>, RegisterGroupError> {
+    let emul = match operand {
+        VOperand::None => return Ok(None),
+        VOperand::Group(n) => lmul + n,
+        VOperand::Mask | VOperand::Scalar => 0,
+    };
+    if !(-3..=3).contains(&emul) {
+        return Err(RegisterGroupError::IllegalEmul);
+    }
+    let size = 1 << emul.max(0);
+    if register & (size - 1) != 0 {
+        return Err(RegisterGroupError::Misaligned);
+    }
+    Ok(Some((register, register + size)))
+}
+
+// Overlap between a destination and a source is only allowed when:
+// * They have the same EEW, where the groups must then be identical
+// * The destination EEW is smaller, and the overlap is the lowest-numbered
+//   part of the source group
+// * The destination EEW is larger, the source EMUL is at least 1, and the
+//   overlap is the highest-numbered part of the destination group
+fn check_overlap(
+    dest: ((usize, usize), VOperand),
+    src: ((usize, usize), VOperand),
+    lmul: i8,
+) -> Result<(), RegisterGroupError> {
+    let ((dest_start, dest_end), dest_operand) = dest;
+    let ((src_start, src_end), src_operand) = src;
+    if dest_end <= src_start || src_end <= dest_start {
+        return Ok(());
+    }
+    let eew = |operand| match operand {
+        VOperand::Group(n) => n,
+        _ => i8::MIN,
+    };
+    let allowed = match eew(dest_operand).cmp(&eew(src_operand)) {
+        std::cmp::Ordering::Equal => dest_start == src_start && dest_end == src_end,
+        std::cmp::Ordering::Less => dest_start == src_start,
+        std::cmp::Ordering::Greater => lmul + eew(src_operand) >= 0 && dest_end == src_end,
+    };
+    if allowed {
+        Ok(())
+    } else {
+        Err(RegisterGroupError::SourceOverlap)
+    }
+}
+
+fn memory_layout(instruction_bits: u32, sew: i8) -> Option<(VLayout, usize)> {
+    use VOperand::*;
+    // Only the operand fields are needed here, not the opcode
+    let inst = VMemType::new(0, instruction_bits);
+    let eew = log2(inst.eew()?) - sew;
+    let is_load = instruction_bits & 0x7f == 0x07;
+    let mut layout = match (inst.addressing(), inst.lumop()) {
+        (VMemAddressing::UnitStride, 0b01011) => VLayout::new(Mask, None, None),
+        (VMemAddressing::UnitStride, _) | (VMemAddressing::Strided, _) => {
+            VLayout::new(Group(eew), None, None)
+        }
+        _ => VLayout {
+            // Indexed segment loads may not overlap the index at all
+            no_overlap: inst.nfields() > 1,
+            ..VLayout::new(Group(0), None, Group(eew))
+        },
+    };
+    layout.vd_is_source = !is_load;
+    Some((layout, inst.nfields() as usize))
+}
+
+// How SEW/LMUL size the operands of a vector instruction
+enum OperandSizes {
+    // Not a vector instruction, or vset{i}vl{i}
+    Unsized,
+    // Whole register accesses and moves: the number of registers, and
+    // whether vs2 is such a group too, as for moves
+    Whole(usize, bool),
+    // The layout, the vs1 operand and the number of segment fields
+    Grouped(VLayout, VOperand, usize),
+}
+
+fn operand_sizes(
+    instruction_bits: u32,
+    opcode: insts::InstructionOpcode,
+    sew: u32,
+) -> OperandSizes {
+    let (layout, nfields) = match instruction_bits & 0x7f {
+        0x57 => match arithmetic_layout(instruction_bits, opcode) {
+            Some(layout) => (layout, 1),
+            None => return OperandSizes::Unsized,
+        },
+        // The other widths are scalar floating point accesses
+        0x07 | 0x27 if VMemType::new(0, instruction_bits).eew().is_some() => {
+            // Whole register accesses and moves ignore SEW/LMUL, they only
+            // need the group to be aligned to its size
+            if x(instruction_bits, 26, 2, 0) == 0 && x(instruction_bits, 20, 5, 0) == 0b01000 {
+                let size = x(instruction_bits, 29, 3, 0) as usize + 1;
+                return OperandSizes::Whole(size, false);
+            }
+            match memory_layout(instruction_bits, log2(sew)) {
+                Some(layout) => layout,
+                None => return OperandSizes::Unsized,
+            }
+        }
+        _ => return OperandSizes::Unsized,
+    };
+    if matches!(
+        opcode,
+        insts::OP_VMV1R_V | insts::OP_VMV2R_V | insts::OP_VMV4R_V | insts::OP_VMV8R_V
+    ) {
+        let size = x(instruction_bits, 15, 5, 0) as usize + 1;
+        return OperandSizes::Whole(size, true);
+    }
+    // vrgatherei16 always uses 16-bit indices
+    let vs1_operand = if opcode == insts::OP_VRGATHEREI16_VV {
+        VOperand::Group(4 - log2(sew))
+    } else {
+        layout.vs1
+    };
+    OperandSizes::Grouped(layout, vs1_operand, nfields)
+}
+
+// Check a decoded vector instruction against the register group rules of
+// the spec for the given SEW/LMUL, so a VM can trap at decode time instead
+// of during execution. Non-vector instructions are accepted as is.
+pub fn check_register_groups(
+    instruction_bits: u32,
+    instruction: Instruction,
+    sew: u32,
+    lmul: Lmul,
+) -> Result<(), RegisterGroupError> {
+    let vd = rd(instruction_bits);
+    let lmul = lmul.log2();
+    let (layout, vs1_operand, nfields) =
+        match operand_sizes(instruction_bits, extract_opcode(instruction), sew) {
+            OperandSizes::Unsized => return Ok(()),
+            OperandSizes::Whole(size, moves) => {
+                let registers = if moves {
+                    vd | rs2(instruction_bits)
+                } else {
+                    vd
+                };
+                return if registers & (size - 1) == 0 {
+                    Ok(())
+                } else {
+                    Err(RegisterGroupError::Misaligned)
+                };
+            }
+            OperandSizes::Grouped(layout, vs1_operand, nfields) => (layout, vs1_operand, nfields),
+        };
+
+    let dest = register_group(vd, layout.vd, lmul)?;
+    let vs1 = register_group(rs1(instruction_bits), vs1_operand, lmul)?;
+    let vs2 = register_group(rs2(instruction_bits), layout.vs2, lmul)?;
+    // Segment stores need their fields to fit as much as loads do
+    let dest = match dest {
+        Some((start, end)) => (start, start + (end - start) * nfields),
+        None => return Ok(()),
+    };
+    if nfields > 1 && (dest.1 - dest.0 > 8 || dest.1 > 32) {
+        return Err(RegisterGroupError::SegmentTooLarge);
+    }
+    if layout.vd_is_source {
+        return Ok(());
+    }
+    let dest = (dest, layout.vd);
+
+    let masked = !vm(instruction_bits);
+    let writes_mask = matches!(layout.vd, VOperand::Mask | VOperand::Scalar);
+    if masked && dest.0 .0 == 0 && (!writes_mask || layout.no_overlap) {
+        return Err(RegisterGroupError::MaskOverlap);
+    }
+
+    for (src, operand) in [(vs1, vs1_operand), (vs2, layout.vs2)] {
+        let src = match (src, operand) {
+            (Some(src), VOperand::Group(_) | VOperand::Mask) => src,
+            _ => continue,
+        };
+        if layout.no_overlap {
+            if dest.0 .1 > src.0 && src.1 > dest.0 .0 {
+                return Err(RegisterGroupError::SourceOverlap);
+            }
+        } else {
+            check_overlap(dest, (src, operand), lmul)?;
+        }
+    }
+    Ok(())
+}
+
+// Registers the vd, vs1 and vs2 fields of a vector instruction cover under
+// SEW/LMUL, None for fields naming no vector register. The vd range of a
+// segment access spans all of its fields.
+pub fn register_group_ranges(
+    instruction_bits: u32,
+    opcode: insts::InstructionOpcode,
+    sew: u32,
+    lmul: Lmul,
+) -> Result<[Option<Range<usize>>; 3], RegisterGroupError> {
+    let vd = rd(instruction_bits);
+    let vs2 = rs2(instruction_bits);
+    let lmul = lmul.log2();
+    match operand_sizes(instruction_bits, opcode, sew) {
+        OperandSizes::Unsized => Ok([None, None, None]),
+        OperandSizes::Whole(size, moves) => {
+            let registers = if moves { vd | vs2 } else { vd };
+            if registers & (size - 1) != 0 {
+                return Err(RegisterGroupError::Misaligned);
+            }
+            Ok([Some(vd..vd + size), None, moves.then(|| vs2..vs2 + size)])
+        }
+        OperandSizes::Grouped(layout, vs1_operand, nfields) => {
+            let range = |register, operand| {
+                register_group(register, operand, lmul)
+                    .map(|group| group.map(|(start, end)| start..end))
+            };
+            let dest =
+                range(vd, layout.vd)?.map(|group| group.start..group.start + group.len() * nfields);
+            if matches!(&dest, Some(group) if nfields > 1 && (group.len() > 8 || group.end > 32)) {
+                return Err(RegisterGroupError::SegmentTooLarge);
+            }
+            Ok([
+                dest,
+                range(rs1(instruction_bits), vs1_operand)?,
+                range(vs2, layout.vs2)?,
+            ])
+        }
+    }
+}
+
+#[cfg(all(test, feature = "rvv"))]
+mod tests {
+    use super::*;
+    use crate::rvv_decoder::factory_with_config;
+
+    fn decode(instruction_bits: u32, config: &FactoryConfig) -> Option<insts::InstructionOpcode> {
+        factory_with_config(instruction_bits, config).map(extract_opcode)
+    }
+
+    // Encodings from llvm-mc -triple=riscv64 -mattr=+v -show-encoding
+    #[test]
+    fn test_memory_builders() {
+        let config = FactoryConfig::new::<u64>(1);
+        let cases: [(u32, Option<insts::InstructionOpcode>); 11] = [
+            (0x02050407, Some(insts::OP_VLE8_V)),     // vle8.v v8, (a0)
+            (0x00057407, Some(insts::OP_VLE64_V)),    // vle64.v v8, (a0), v0.t
+            (0x0205e227, Some(insts::OP_VSE32_V)),    // vse32.v v4, (a1)
+            (0x02b50087, Some(insts::OP_VLM_V)),      // vlm.v v1, (a0)
+            (0x02b500a7, Some(insts::OP_VSM_V)),      // vsm.v v1, (a0)
+            (0x0ab56407, Some(insts::OP_VLSE32_V)),   // vlse32.v v8, (a0), a1
+            (0x0ab57427, Some(insts::OP_VSSE64_V)),   // vsse64.v v8, (a0), a1
+            (0x07050407, Some(insts::OP_VLUXEI8_V)),  // vluxei8.v v8, (a0), v16
+            (0x0f056427, Some(insts::OP_VSOXEI32_V)), // vsoxei32.v v8, (a0), v16
+            // vlm.v with nf=1 and vle8.v with lumop 0b00001 are reserved
+            (0x22b50087, None),
+            (0x02150407, None),
+        ];
+        for (instruction_bits, opcode) in cases {
+            assert_eq!(
+                decode(instruction_bits, &config),
+                opcode,
+                "{:x}",
+                instruction_bits
+            );
+        }
+        // vlseg8e8.v v24, (a0) ends at v31, from v25 it would run past it
+        assert_eq!(decode(0xe2050c07, &config), Some(insts::OP_VLE8_V));
+        assert_eq!(decode(0xe2050c87, &config), None);
+
+        let inst = VMemType::new(insts::OP_VLE8_V, 0xe2050c07);
+        assert_eq!((inst.vd(), inst.rs1(), inst.vm()), (24, 10, true));
+        assert_eq!((inst.nfields(), inst.eew()), (8, Some(8)));
+        assert_eq!(inst.addressing(), VMemAddressing::UnitStride);
+        let inst = VMemType::new(insts::OP_VSOXEI32_V, 0x0f056427);
+        assert_eq!((inst.rs2(), inst.eew()), (16, Some(32)));
+        assert_eq!(inst.addressing(), VMemAddressing::IndexedOrdered);
+        // flw fa0, 0(a0) shares the LOAD-FP major opcode, its width is no EEW
+        assert_eq!(VMemType::new(0, 0x00052507).eew(), None);
+    }
+
+    #[test]
+    fn test_whole_register_builder() {
+        let config = FactoryConfig::new::<u64>(1);
+        let cases = [
+            (0x02850187, insts::OP_VL1RE8_V),  // vl1r.v v3, (a0)
+            (0x22855107, insts::OP_VL2RE16_V), // vl2re16.v v2, (a0)
+            (0x62856207, insts::OP_VL4RE32_V), // vl4re32.v v4, (a0)
+            (0xe2857407, insts::OP_VL8RE64_V), // vl8re64.v v8, (a0)
+            (0x028501a7, insts::OP_VS1R_V),    // vs1r.v v3, (a0)
+            (0x22850127, insts::OP_VS2R_V),    // vs2r.v v2, (a0)
+            (0x62850227, insts::OP_VS4R_V),    // vs4r.v v4, (a0)
+            (0xe2850427, insts::OP_VS8R_V),    // vs8r.v v8, (a0)
+        ];
+        for (instruction_bits, opcode) in cases {
+            assert_eq!(
+                decode(instruction_bits, &config),
+                Some(opcode),
+                "{:x}",
+                instruction_bits
+            );
+            // only nf of 0, 1, 3 and 7 are defined
+            for nf in [2, 4, 5, 6] {
+                let reserved = instruction_bits & !0xe0000000 | nf << 29;
+                assert_eq!(decode(reserved, &config), None, "{:x}", reserved);
+            }
+        }
+        // whole register stores have no element width
+        assert_eq!(decode(0x028551a7, &config), None);
+    }
+
+    #[test]
+    fn test_float_builders() {
+        // decode-only, so rejected unless a profile is given
+        assert_eq!(decode(0x02461457, &FactoryConfig::new::<u64>(1)), None);
+        let config = FactoryConfig::new::<u64>(1).with_vector_profile(VectorProfile::V);
+        let cases = [
+            (0x02461457, insts::OP_VFADD_VV),     // vfadd.vv v8, v4, v12
+            (0x00455457, insts::OP_VFADD_VF),     // vfadd.vf v8, v4, fa0, v0.t
+            (0xb245d457, insts::OP_VFMACC_VF),    // vfmacc.vf v8, fa1, v4
+            (0x42055457, insts::OP_VFMV_S_F),     // vfmv.s.f v8, fa0
+            (0x5e055457, insts::OP_VFMV_V_F),     // vfmv.v.f v8, fa0
+            (0x62465057, insts::OP_VMFEQ_VF),     // vmfeq.vf v0, v4, fa2
+            (0x4a409457, insts::OP_VFCVT_X_F_V),  // vfcvt.x.f.v v8, v4
+            (0xc2455457, insts::OP_VFWADD_VF),    // vfwadd.vf v8, v4, fa0
+            (0x068190d7, insts::OP_VFREDUSUM_VS), // vfredusum.vs v1, v8, v3
+            (0x42801557, insts::OP_VFMV_F_S),     // vfmv.f.s fa0, v8
+        ];
+        for (instruction_bits, opcode) in cases {
+            assert_eq!(
+                decode(instruction_bits, &config),
+                Some(opcode),
+                "{:x}",
+                instruction_bits
+            );
+        }
+        let instruction = factory_with_config(0x00455457, &config).unwrap();
+        let i = VFtype(instruction);
+        assert_eq!((i.vd(), i.fs1(), i.vs2(), i.vm()), (8, 10, 4, false));
+        let instruction = factory_with_config(0xb245d457, &config).unwrap();
+        assert_eq!(VFtype(instruction).fs1(), 11);
+        // rd of vfmv.f.s names fa0, not a vector register
+        let instruction = factory_with_config(0x42801557, &config).unwrap();
+        let r = Rtype(instruction);
+        assert_eq!((r.rd(), r.rs1(), r.rs2()), (10, 0, 8));
+        // vfmv.f.s takes neither vs1 nor a mask
+        assert_eq!(decode(0x42809557, &config), None);
+        assert_eq!(decode(0x40801557, &config), None);
+    }
+
+    #[test]
+    fn test_vector_profiles() {
+        let config = FactoryConfig::new::<u64>(1);
+        let zve32x = config.with_vector_profile(VectorProfile::Zve32x);
+        let zve32f = config.with_vector_profile(VectorProfile::Zve32f);
+        let zve64x = config.with_vector_profile(VectorProfile::Zve64x);
+        let v = config.with_vector_profile(VectorProfile::V);
+        // vsetvli a0, a1, e64, m1 and e32, m1
+        assert_eq!(decode(0x0185f557, &zve32x), None);
+        assert_eq!(decode(0x0185f557, &zve64x), Some(insts::OP_VSETVLI));
+        assert_eq!(decode(0x0105f557, &zve32x), Some(insts::OP_VSETVLI));
+        // vsetivli a0, 4, e64, m1, and vlmul=4 which sets vill whatever ELEN is
+        assert_eq!(decode(0xcd827557, &zve32x), None);
+        assert_eq!(decode(0xcd827557, &zve64x), Some(insts::OP_VSETIVLI));
+        assert_eq!(decode(0x0045f557, &zve32x), Some(insts::OP_VSETVLI));
+        // vle64.v v8, (a0), v0.t and vl1re64.v v8, (a0)
+        assert_eq!(decode(0x00057407, &zve32x), None);
+        assert_eq!(decode(0x00057407, &zve64x), Some(insts::OP_VLE64_V));
+        assert_eq!(decode(0x02857407, &zve32x), None);
+        assert_eq!(decode(0x02857407, &zve64x), Some(insts::OP_VL1RE64_V));
+        // vle32.v v8, (a0) fits in Zve32x
+        assert_eq!(decode(0x02056407, &zve32x), Some(insts::OP_VLE32_V));
+        // vfadd.vv v8, v4, v12, vfadd.vf v8, v4, fa0, v0.t and vfmv.f.s fa0, v8
+        for instruction_bits in [0x02461457, 0x00455457, 0x42801557] {
+            assert_eq!(decode(instruction_bits, &zve32x), None);
+            assert_eq!(decode(instruction_bits, &zve64x), None);
+            assert!(decode(instruction_bits, &zve32f).is_some());
+            assert!(decode(instruction_bits, &v).is_some());
+        }
+        // integer operations are in every profile
+        assert_eq!(decode(0x02860257, &zve32x), Some(insts::OP_VADD_VV));
+    }
+
+    // Encodings put together from the template entries, llvm-mc 14 has no
+    // vector crypto
+    #[cfg(feature = "rvvk")]
+    #[test]
+    fn test_crypto_builders() {
+        use crate::rvvk_decoder::factory_with_config;
+        let config = FactoryConfig::new::<u64>(1).with_vector_crypto();
+        let decode = |instruction_bits| factory_with_config(instruction_bits, &config);
+        let cases = [
+            (0x5640b457, insts::OP_VROR_VI),    // vror.vi v8, v4, 33
+            (0x5040b457, insts::OP_VROR_VI),    // vror.vi v8, v4, 1, v0.t
+            (0xd64fb457, insts::OP_VWSLL_VI),   // vwsll.vi v8, v4, 31
+            (0x04460457, insts::OP_VANDN_VV),   // vandn.vv v8, v4, v12, v0.t
+            (0x32456457, insts::OP_VCLMUL_VX),  // vclmul.vx v8, v4, a0
+            (0xa641a477, insts::OP_VAESEF_VS),  // vaesef.vs v8, v4
+            (0xa241a477, insts::OP_VAESEF_VV),  // vaesef.vv v8, v4
+            (0xa643a477, insts::OP_VAESZ_VS),   // vaesz.vs v8, v4
+            (0x8a41a477, insts::OP_VAESKF1_VI), // vaeskf1.vi v8, v4, 3
+            (0xb2462477, insts::OP_VGHSH_VV),   // vghsh.vv v8, v4, v12
+            (0xb6462477, insts::OP_VSHA2MS_VV), // vsha2ms.vv v8, v4, v12
+        ];
+        for (instruction_bits, opcode) in cases {
+            let instruction = decode(instruction_bits);
+            assert_eq!(
+                instruction.map(extract_opcode),
+                Some(opcode),
+                "{:x}",
+                instruction_bits
+            );
+            // nothing decodes without vector crypto
+            let plain = FactoryConfig::new::<u64>(1);
+            assert_eq!(factory_with_config(instruction_bits, &plain), None);
+        }
+
+        // uimm[5] of vror.vi comes from bit 26
+        let i = VItype(decode(0x5640b457).unwrap());
+        assert_eq!((i.vd(), i.vs2(), i.immediate_u(), i.vm()), (8, 4, 33, true));
+        let i = VItype(decode(0x5040b457).unwrap());
+        assert_eq!((i.immediate_u(), i.vm()), (1, false));
+        // the element group instructions are unmasked only, and their vs1
+        // field picks the operation
+        assert_eq!(decode(0xa441a477), None);
+        assert_eq!(decode(0xa649a477), None);
+        assert_eq!(decode(0xb0462477), None);
+        let i = VVtype(decode(0xa641a477).unwrap());
+        assert_eq!((i.vd(), i.vs2()), (8, 4));
+        let i = VVtype(decode(0xb6462477).unwrap());
+        assert_eq!((i.vd(), i.vs1(), i.vs2()), (8, 12, 4));
+    }
+
+    #[test]
+    fn test_vtype() {
+        assert_eq!(VType::decode(0x04), Err(VTypeError::ReservedLmul));
+        assert_eq!(VType::decode(0x20), Err(VTypeError::ReservedSew));
+        assert_eq!(VType::decode(0x38), Err(VTypeError::ReservedSew));
+        assert_eq!(VType::decode(0x100), Err(VTypeError::ReservedBits));
+        // reserved settings read as vill, which has no SEW/LMUL
+        for bits in [0x04, 0x20, 0x100, 1 << 63] {
+            let vtype = VType::new(bits);
+            assert_eq!(vtype, VType::ILLEGAL);
+            assert!(vtype.vill());
+            assert_eq!(
+                (vtype.sew(), vtype.lmul(), vtype.vlmax(128)),
+                (None, None, 0)
+            );
+        }
+        // (vtype, SEW, LMUL, VLMAX for VLEN=128)
+        let cases = [
+            (0x05, 8, Lmul::Mf8, 2),
+            (0x06, 8, Lmul::Mf4, 4),
+            (0x17, 32, Lmul::Mf2, 2),
+            (0x10, 32, Lmul::M1, 4),
+            (0x09, 16, Lmul::M2, 16),
+            (0x1b, 64, Lmul::M8, 16),
+        ];
+        for (bits, sew, lmul, vlmax) in cases {
+            let vtype = VType::new(bits);
+            assert_eq!(
+                (vtype.sew(), vtype.lmul()),
+                (Some(sew), Some(lmul)),
+                "{:x}",
+                bits
+            );
+            assert_eq!(vtype.vlmax(128), vlmax, "{:x}", bits);
+            assert!(!vtype.vill() && !vtype.ta() && !vtype.ma());
+        }
+        let vtype = VType::new(0xd0);
+        assert!(vtype.ta() && vtype.ma());
+
+        // vsetvli a0, a1, e32, m1 and the same with vlmul=4, which still
+        // decodes as the VM sets vill for it
+        let config = FactoryConfig::new::<u64>(1);
+        let vsetvli = factory_with_config(0x0105f557, &config).unwrap();
+        assert_eq!(VType::from_instruction(vsetvli), Some(VType::new(0x10)));
+        let vsetvli = factory_with_config(0x0045f557, &config).unwrap();
+        assert_eq!(VType::from_instruction(vsetvli), Some(VType::ILLEGAL));
+    }
+
+    // (word, SEW, LMUL, expected), words from llvm-mc or, for the reserved
+    // ones it rejects, edited from them
+    const REGISTER_GROUPS: [(u32, u32, Lmul, Result<(), RegisterGroupError>); 21] = [
+        // vadd.vv v0, v8, v12, v0.t writes the mask, vmseq.vv may
+        (
+            0x00860057,
+            32,
+            Lmul::M1,
+            Err(RegisterGroupError::MaskOverlap),
+        ),
+        (0x60860057, 32, Lmul::M1, Ok(())),
+        // vadd.vv v1, v8, v12
+        (0x028600d7, 32, Lmul::M1, Ok(())),
+        (
+            0x028600d7,
+            32,
+            Lmul::M4,
+            Err(RegisterGroupError::Misaligned),
+        ),
+        // vwadd.vv v8, v4, v6, its destination has EMUL 16 under m8
+        (0xc6432457, 32, Lmul::M2, Ok(())),
+        (
+            0xc6432457,
+            32,
+            Lmul::M8,
+            Err(RegisterGroupError::IllegalEmul),
+        ),
+        // vwadd.vv v4, v4, v6 overlaps the lower half of its destination,
+        // vwadd.vv v4, v6, v8 the upper one
+        (
+            0xc6432257,
+            32,
+            Lmul::M2,
+            Err(RegisterGroupError::SourceOverlap),
+        ),
+        (0xc6642257, 32, Lmul::M2, Ok(())),
+        // vredsum.vs v1, v8, v3 only groups vs2
+        (0x0281a0d7, 32, Lmul::M4, Ok(())),
+        // vlseg4e32.v v8, (a0) and vsseg4e32.v v8, (a0) need 4 groups
+        (0x62056407, 32, Lmul::M2, Ok(())),
+        (
+            0x62056407,
+            32,
+            Lmul::M4,
+            Err(RegisterGroupError::SegmentTooLarge),
+        ),
+        (0x62056427, 32, Lmul::M2, Ok(())),
+        (
+            0x62056427,
+            32,
+            Lmul::M4,
+            Err(RegisterGroupError::SegmentTooLarge),
+        ),
+        // vlseg2e32.v v28, (a0) runs past v31 under m4
+        (0x22056e07, 32, Lmul::M2, Ok(())),
+        (
+            0x22056e07,
+            32,
+            Lmul::M4,
+            Err(RegisterGroupError::SegmentTooLarge),
+        ),
+        // vl2re32.v v2, (a0) and v3, whatever LMUL is
+        (0x22856107, 32, Lmul::M8, Ok(())),
+        (
+            0x22856187,
+            32,
+            Lmul::Mf2,
+            Err(RegisterGroupError::Misaligned),
+        ),
+        // vmv2r.v v2, v4 and v2, v5
+        (0x9e40b157, 32, Lmul::M1, Ok(())),
+        (
+            0x9e50b157,
+            32,
+            Lmul::M1,
+            Err(RegisterGroupError::Misaligned),
+        ),
+        // flw fa0, 8(a0) and fsd fa0, 1024(a0) look like vector accesses
+        // with lumop 0b01000 and nf=2, but their widths are scalar ones
+        (0x00852507, 32, Lmul::M4, Ok(())),
+        (0x40a53027, 32, Lmul::M4, Ok(())),
+    ];
+
+    #[test]
+    fn test_check_register_groups() {
+        let config = FactoryConfig::new::<u64>(1);
+        for (instruction_bits, sew, lmul, expected) in REGISTER_GROUPS {
+            // scalar floating point words are not in the rvv tables
+            let instruction = factory_with_config(instruction_bits, &config).unwrap_or(0);
+            let found = check_register_groups(instruction_bits, instruction, sew, lmul);
+            assert_eq!(found, expected, "{:x}", instruction_bits);
+            // the ranges only see the sizes, not the overlaps
+            let ranges =
+                register_group_ranges(instruction_bits, extract_opcode(instruction), sew, lmul);
+            match expected {
+                Err(RegisterGroupError::MaskOverlap | RegisterGroupError::SourceOverlap)
+                | Ok(()) => {
+                    assert!(ranges.is_ok(), "{:x}", instruction_bits)
+                }
+                Err(error) => assert_eq!(ranges.map(|_| ()), Err(error), "{:x}", instruction_bits),
+            }
+        }
+        assert_eq!(
+            register_group_ranges(0x00852507, 0, 32, Lmul::M4),
+            Ok([None, None, None])
+        );
+        assert_eq!(
+            register_group_ranges(0x62056427, insts::OP_VSE32_V, 32, Lmul::M2),
+            Ok([Some(8..16), None, None])
+        );
+    }
+}
@@ -0,0 +1,14 @@
+# A relocatable RV64 object for the tests in src/elf.rs, assembled with
+# llvm-mc -triple=riscv64 -mattr=+m,+c -filetype=obj hints.s -o hints.o
+	.text
+	.globl	_start
+_start:
+	addi	a0, zero, 1
+	c.li	a1, 2
+	mul	a0, a0, a1
+	jal	ra, hint
+	ebreak
+hint:
+	# c.mv zero, a0, a HINT ckb-vm decodes from version 1 on
+	.2byte	0x802a
+	c.jr	ra
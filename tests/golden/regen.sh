@@ -0,0 +1,13 @@
+#!/bin/sh
+# Regenerates rv64.txt and rvv.txt with GNU binutils and records its version
+# in binutils-version, see README.md
+set -e
+cd "$(dirname "$0")"
+PREFIX=${PREFIX:-riscv64-unknown-elf-}
+for name in rv64 rvv; do
+    "${PREFIX}as" -march=rv64gcv_zba_zbb_zbc_zbs "$name.s" -o "$name.o"
+    "${PREFIX}objdump" -d -M no-aliases "$name.o" \
+        | grep '^ *[0-9a-f]*:' | sed 's/ <[^>]*>$//' > "$name.txt"
+    rm "$name.o"
+done
+"${PREFIX}objdump" --version | head -n 1 > binutils-version
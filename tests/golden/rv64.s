@@ -0,0 +1,164 @@
+# Base integer, M, Zba/Zbb/Zbc/Zbs and C encodings for the disassembler
+# golden test, see README.md
+	.option norvc
+	lui	a0, 0x12345
+	lui	t6, 0xfffff
+	auipc	gp, 0x2
+	auipc	a0, 0
+	jal	ra, 8
+	jal	zero, -4
+	jal	s1, 2048
+	jalr	ra, 0(a0)
+	jalr	zero, 0(ra)
+	jalr	t0, -12(a1)
+	beq	a0, a1, 16
+	bne	s0, zero, -16
+	blt	t0, t1, 4094
+	bge	zero, a2, -4096
+	bltu	a3, a4, 8
+	bgeu	a5, a6, 12
+	lb	a0, -1(sp)
+	lh	a1, 2(s0)
+	lw	a2, 2047(a3)
+	ld	a3, -2048(a4)
+	lbu	a4, 0(t0)
+	lhu	a5, 6(t1)
+	lwu	a6, 8(t2)
+	sb	a0, -1(sp)
+	sh	a1, 2(s0)
+	sw	a2, 2047(a3)
+	sd	a3, -2048(a4)
+	addi	zero, zero, 0
+	addi	a0, zero, -5
+	addi	a0, a1, 0
+	addi	sp, sp, -16
+	slti	a0, a1, 7
+	sltiu	a0, a1, 1
+	xori	a0, a1, -1
+	ori	a0, a1, 255
+	andi	a0, a1, 0x7ff
+	slli	a0, a1, 3
+	srli	a0, a1, 63
+	srai	a0, a1, 32
+	add	a0, a1, a2
+	sub	a0, zero, a2
+	sll	s2, s3, s4
+	slt	s5, s6, s7
+	sltu	s8, zero, s9
+	xor	s10, s11, t3
+	srl	t4, t5, t6
+	sra	gp, tp, ra
+	or	a7, a6, a5
+	and	a4, a3, a2
+	fence	iorw, iorw
+	fence	rw, w
+	fence.i
+	ecall
+	ebreak
+	addiw	a0, a1, 0
+	addiw	a0, a1, -1
+	slliw	a0, a1, 31
+	srliw	a0, a1, 1
+	sraiw	a0, a1, 5
+	addw	a0, a1, a2
+	subw	a0, zero, a2
+	sllw	a0, a1, a2
+	srlw	a0, a1, a2
+	sraw	a0, a1, a2
+	mul	a0, a1, a2
+	mulh	a0, a1, a2
+	mulhsu	a0, a1, a2
+	mulhu	a0, a1, a2
+	div	a0, a1, a2
+	divu	a0, a1, a2
+	rem	a0, a1, a2
+	remu	a0, a1, a2
+	mulw	a0, a1, a2
+	divw	a0, a1, a2
+	divuw	a0, a1, a2
+	remw	a0, a1, a2
+	remuw	a0, a1, a2
+	add.uw	a0, a1, a2
+	add.uw	a0, a1, zero
+	sh1add	a0, a1, a2
+	sh2add	a0, a1, a2
+	sh3add	a0, a1, a2
+	sh1add.uw	a0, a1, a2
+	sh2add.uw	a0, a1, a2
+	sh3add.uw	a0, a1, a2
+	slli.uw	a0, a1, 40
+	andn	a0, a1, a2
+	orn	a0, a1, a2
+	xnor	a0, a1, a2
+	clz	a0, a1
+	clzw	a0, a1
+	ctz	a0, a1
+	ctzw	a0, a1
+	cpop	a0, a1
+	cpopw	a0, a1
+	max	a0, a1, a2
+	maxu	a0, a1, a2
+	min	a0, a1, a2
+	minu	a0, a1, a2
+	sext.b	a0, a1
+	sext.h	a0, a1
+	zext.h	a0, a1
+	rol	a0, a1, a2
+	rolw	a0, a1, a2
+	ror	a0, a1, a2
+	rorw	a0, a1, a2
+	rori	a0, a1, 33
+	roriw	a0, a1, 7
+	orc.b	a0, a1
+	rev8	a0, a1
+	clmul	a0, a1, a2
+	clmulh	a0, a1, a2
+	clmulr	a0, a1, a2
+	bclr	a0, a1, a2
+	bclri	a0, a1, 63
+	bext	a0, a1, a2
+	bexti	a0, a1, 1
+	binv	a0, a1, a2
+	binvi	a0, a1, 2
+	bset	a0, a1, a2
+	bseti	a0, a1, 3
+	.option rvc
+	c.addi4spn	a0, sp, 16
+	c.lw	a1, 4(a2)
+	c.ld	a3, 248(a4)
+	c.sw	a5, 124(s0)
+	c.sd	s1, 8(a0)
+	c.nop
+	c.addi	a0, -1
+	c.addiw	a1, 31
+	c.addiw	a1, 0
+	c.li	a2, -32
+	c.addi16sp	sp, -512
+	c.addi16sp	sp, 496
+	c.lui	a3, 1
+	c.lui	a3, 0xfffe0
+	c.srli	s0, 63
+	c.srai	s1, 1
+	c.andi	a4, -1
+	c.sub	a0, a1
+	c.xor	a2, a3
+	c.or	a4, a5
+	c.and	s0, s1
+	c.subw	a0, s1
+	c.addw	a2, a3
+	c.j	-2048
+	c.j	2046
+	c.beqz	a0, -256
+	c.bnez	s1, 254
+	c.slli	t6, 1
+	c.slli	a0, 63
+	c.lwsp	a0, 252(sp)
+	c.ldsp	ra, 504(sp)
+	c.jr	ra
+	c.jr	a0
+	c.mv	a0, a1
+	c.ebreak
+	c.jalr	t0
+	c.add	a0, a1
+	c.swsp	a0, 252(sp)
+	c.sdsp	ra, 504(sp)
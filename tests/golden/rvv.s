@@ -0,0 +1,155 @@
+# Vector encodings for the disassembler golden test, see README.md
+	vsetvli	a0, a1, e32, m2, ta, ma
+	vsetvli	t0, zero, e8, mf2, tu, mu
+	vsetvli	zero, a2, e64, m8, ta, mu
+	vsetivli	a0, 31, e16, mf8, tu, ma
+	vsetvl	a0, a1, a2
+	vle8.v	v1, (a0)
+	vle16.v	v2, (a1), v0.t
+	vle32.v	v3, (a2)
+	vle64.v	v4, (a3)
+	vlm.v	v6, (a5)
+	vse8.v	v1, (a0)
+	vse64.v	v8, (sp), v0.t
+	vsm.v	v6, (a5)
+	vlse32.v	v1, (a0), a1
+	vsse16.v	v2, (a2), t0, v0.t
+	vluxei8.v	v1, (a0), v2
+	vloxei64.v	v3, (a1), v4, v0.t
+	vsuxei16.v	v5, (a2), v6
+	vsoxei32.v	v7, (a3), v8
+	vlseg2e32.v	v8, (a0)
+	vsseg8e8.v	v16, (a1)
+	vlsseg3e16.v	v4, (a0), a1
+	vluxseg4ei8.v	v4, (a0), v12
+	vl1re8.v	v1, (a0)
+	vl2re16.v	v2, (a1)
+	vl4re32.v	v4, (a2)
+	vl8re64.v	v8, (a3)
+	vs1r.v	v1, (a0)
+	vs8r.v	v8, (a1)
+	vadd.vv	v1, v2, v3
+	vadd.vx	v1, v2, a0, v0.t
+	vadd.vi	v1, v2, -16
+	vsub.vv	v4, v5, v6
+	vrsub.vx	v4, v5, zero
+	vrsub.vi	v4, v5, 15
+	vand.vi	v1, v2, -1
+	vsll.vi	v1, v2, 31
+	vsrl.vx	v1, v2, t1
+	vsra.vv	v1, v2, v3, v0.t
+	vnsrl.wi	v1, v2, 3
+	vnclipu.wx	v1, v2, a0
+	vslideup.vi	v1, v2, 7
+	vslidedown.vx	v1, v2, a1
+	vrgather.vv	v4, v8, v12
+	vrgather.vi	v4, v8, 5
+	vrgatherei16.vv	v4, v8, v12
+	vmseq.vv	v0, v2, v3
+	vmsne.vi	v1, v2, 0
+	vmsltu.vx	v1, v2, a3, v0.t
+	vmsgt.vi	v1, v2, -3
+	vminu.vv	v1, v2, v3
+	vmax.vx	v1, v2, a4
+	vadc.vvm	v1, v2, v3, v0
+	vadc.vim	v1, v2, 4, v0
+	vmadc.vvm	v1, v2, v3, v0
+	vmadc.vv	v1, v2, v3
+	vsbc.vxm	v1, v2, a0, v0
+	vmsbc.vx	v1, v2, a0
+	vmerge.vvm	v1, v2, v3, v0
+	vmerge.vxm	v1, v2, a0, v0
+	vmerge.vim	v1, v2, -1, v0
+	vmv.v.v	v1, v2
+	vmv.v.x	v1, a0
+	vmv.v.i	v1, -7
+	vmv1r.v	v1, v2
+	vmv2r.v	v2, v4
+	vmv4r.v	v4, v8
+	vmv8r.v	v8, v16
+	vsaddu.vv	v1, v2, v3
+	vssub.vx	v1, v2, a0
+	vsmul.vv	v1, v2, v3
+	vssrl.vi	v1, v2, 2
+	vwredsum.vs	v1, v2, v3
+	vredsum.vs	v1, v2, v3, v0.t
+	vredmaxu.vs	v1, v2, v3
+	vaadd.vv	v1, v2, v3
+	vasubu.vx	v1, v2, a0
+	vmul.vv	v1, v2, v3
+	vmulh.vx	v1, v2, a0
+	vdivu.vv	v1, v2, v3
+	vrem.vx	v1, v2, a0
+	vwaddu.vv	v2, v4, v6
+	vwadd.wx	v2, v4, a0
+	vwmul.vv	v2, v4, v6
+	vwmulsu.vx	v2, v4, a0
+	vmacc.vv	v1, v2, v3
+	vnmsac.vx	v1, a0, v3
+	vmadd.vv	v1, v2, v3, v0.t
+	vnmsub.vx	v1, a0, v3
+	vwmaccu.vv	v2, v4, v6
+	vwmaccus.vx	v2, a0, v6
+	vmv.x.s	a0, v1
+	vmv.s.x	v1, a0
+	vcpop.m	a0, v1
+	vcpop.m	a0, v1, v0.t
+	vfirst.m	a1, v2
+	vmsbf.m	v1, v2
+	vmsif.m	v1, v2, v0.t
+	vmsof.m	v1, v2
+	viota.m	v1, v2
+	vid.v	v1
+	vid.v	v1, v0.t
+	vzext.vf2	v2, v4
+	vsext.vf8	v8, v16
+	vcompress.vm	v1, v2, v3
+	vmand.mm	v1, v2, v3
+	vmnand.mm	v1, v2, v2
+	vmandn.mm	v1, v2, v3
+	vmxor.mm	v1, v1, v1
+	vmornot.mm	v1, v2, v3
+	vmxnor.mm	v1, v1, v1
+	vslide1up.vx	v1, v2, a0
+	vslide1down.vx	v1, v2, a0
+	vfadd.vv	v1, v2, v3
+	vfadd.vf	v1, v2, fa0
+	vfsub.vf	v1, v2, ft0, v0.t
+	vfrsub.vf	v1, v2, fs11
+	vfmul.vv	v1, v2, v3
+	vfdiv.vf	v1, v2, ft11
+	vfrdiv.vf	v1, v2, fa7
+	vfmin.vv	v1, v2, v3
+	vfmax.vf	v1, v2, fs0
+	vfsgnj.vv	v1, v2, v3
+	vfsgnjn.vv	v1, v2, v2
+	vfsgnjx.vf	v1, v2, fa1
+	vfmacc.vv	v1, v2, v3
+	vfnmacc.vf	v1, fa0, v3
+	vfmsub.vv	v1, v2, v3
+	vfwmacc.vf	v2, fa0, v4
+	vfsqrt.v	v1, v2
+	vfrsqrt7.v	v1, v2, v0.t
+	vfrec7.v	v1, v2
+	vfclass.v	v1, v2
+	vfcvt.xu.f.v	v1, v2
+	vfcvt.f.x.v	v1, v2
+	vfcvt.rtz.x.f.v	v1, v2
+	vfwcvt.f.f.v	v2, v4
+	vfwcvt.f.xu.v	v2, v4
+	vfncvt.rod.f.f.w	v1, v2
+	vfncvt.rtz.xu.f.w	v1, v2
+	vfmv.f.s	fa0, v1
+	vfmv.s.f	v1, fa0
+	vfmv.v.f	v1, ft1
+	vfmerge.vfm	v1, v2, fa0, v0
+	vmfeq.vv	v1, v2, v3
+	vmfle.vf	v1, v2, fa0
+	vmfgt.vf	v1, v2, fa0
+	vmfge.vf	v1, v2, fa0, v0.t
+	vfredusum.vs	v1, v2, v3
+	vfredosum.vs	v1, v2, v3
+	vfwredusum.vs	v1, v2, v3
+	vfslide1up.vf	v1, v2, fa0
+	vfslide1down.vf	v1, v2, fa0
+	vfwadd.wf	v2, v4, fa0